@@ -3,11 +3,17 @@
 
 use std::env;
 
+include!("build/feature_rules.rs");
+
 fn main() {
     // Emit cargo rerun directives for build dependencies
     println!("cargo:rerun-if-changed=build.rs");
     println!("cargo:rerun-if-changed=src/");
     println!("cargo:rerun-if-changed=Cargo.toml");
+    println!("cargo:rerun-if-changed=build/feature_rules.rs");
+
+    // Refuse feature combinations that would only fail at runtime
+    check_feature_combinations();
 
     // Set target-specific compilation flags
     configure_uefi_target();
@@ -25,6 +31,27 @@ fn main() {
     embed_build_info();
 }
 
+fn check_feature_combinations() {
+    // Build scripts see features as CARGO_FEATURE_<NAME>, not as cfg(feature)
+    let enabled: Vec<String> = env::vars()
+        .filter_map(|(k, _)| {
+            k.strip_prefix("CARGO_FEATURE_")
+                .map(|f| f.to_ascii_lowercase().replace('_', "-"))
+        })
+        .collect();
+    let enabled: Vec<&str> = enabled.iter().map(String::as_str).collect();
+
+    let release = env::var("PROFILE").is_ok_and(|p| p == "release");
+    let violations = feature_violations(&enabled, release);
+    if !violations.is_empty() {
+        for v in &violations {
+            eprintln!("error: invalid feature combination: {v}");
+        }
+        eprintln!("(rules: build/feature_rules.rs; `cargo xtask features` checks the matrix)");
+        std::process::exit(1);
+    }
+}
+
 fn configure_uefi_target() {
     // UEFI-specific compilation flags
     println!("cargo:rustc-link-arg=-nostdlib");
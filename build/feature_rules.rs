@@ -0,0 +1,68 @@
+// Feature combinations the loader refuses to build with.
+// Shared by build.rs (enforcement) and `cargo xtask features` (matrix check); keep it
+// plain data so both can `include!` it.
+
+/// Features that must never be enabled together, with the reason shown to the user
+const FEATURE_CONFLICTS: &[(&str, &str, &str)] = &[
+    (
+        "mock-proof",
+        "zk-vk-provisioned",
+        "a provisioned (shippable) build must not accept mock proofs",
+    ),
+];
+
+/// `(feature, needs, reason)`: enabling `feature` without `needs` is meaningless
+const FEATURE_REQUIRES: &[(&str, &str, &str)] = &[
+    (
+        "zk-vk-provisioned",
+        "zk-groth16",
+        "provisioned VKs are only checked by the Groth16 verifier",
+    ),
+    (
+        "zk-bind-manifest",
+        "zk-groth16",
+        "manifest binding changes Groth16 public inputs",
+    ),
+    (
+        "zk-testvectors",
+        "zk-groth16",
+        "the test vectors exercise the Groth16 verifier",
+    ),
+    (
+        "zk-zeroize",
+        "zk-groth16",
+        "only the Groth16 path holds proof/input buffers",
+    ),
+];
+
+/// Like `FEATURE_REQUIRES`, but only for release builds (debug builds may still run the
+/// verifier against placeholder keys)
+const FEATURE_RELEASE_REQUIRES: &[(&str, &str, &str)] = &[(
+    "zk-groth16",
+    "zk-vk-provisioned",
+    "a shipped Groth16 verifier needs real VKs embedded (see Cargo.toml)",
+)];
+
+/// Violations for a set of enabled features (empty when the combination is fine)
+fn feature_violations(enabled: &[&str], release: bool) -> Vec<String> {
+    let on = |f: &str| enabled.contains(&f);
+    let mut out = Vec::new();
+    for (a, b, why) in FEATURE_CONFLICTS {
+        if on(a) && on(b) {
+            out.push(format!("`{a}` conflicts with `{b}`: {why}"));
+        }
+    }
+    for (a, b, why) in FEATURE_REQUIRES {
+        if on(a) && !on(b) {
+            out.push(format!("`{a}` requires `{b}`: {why}"));
+        }
+    }
+    if release {
+        for (a, b, why) in FEATURE_RELEASE_REQUIRES {
+            if on(a) && !on(b) {
+                out.push(format!("`{a}` requires `{b}` in release builds: {why}"));
+            }
+        }
+    }
+    out
+}
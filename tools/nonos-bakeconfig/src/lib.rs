@@ -0,0 +1,240 @@
+//! NONOS nonos-bakeconfig — compile a deployment config bundle into a Rust module (build-time)
+//!
+//! Author: eK (team@nonos.systems) — https://nonos.systems
+//! Purpose: per-product images from one codebase plus a bundle, instead of forking the
+//! source to change compiled-in defaults. Usable as a CLI or from a build script.
+//!
+//! Bundle (TOML):
+//!
+//! ```toml
+//! [product]
+//! name = "nonos-edge"
+//! version = "1.2.0"
+//!
+//! [policy]                          # POLICY_<KEY>: bool | u64 | &str
+//! require_signed_capsule = true
+//!
+//! [[keys]]                          # KEY_<NAME>_ED25519 + TRUSTED_KEYS
+//! name = "release"
+//! ed25519 = "<64 hex chars>"        # or: file = "keys/release.pub" (32 raw bytes)
+//!
+//! [ds]                              # DS_<NAME>: &str
+//! program = "NONOS:ZK:PROGRAM:v1"
+//!
+//! [defaults]                        # DEFAULT_<KEY>: boot.cfg defaults
+//! timeout_secs = 5
+//! ```
+
+use std::{collections::BTreeSet, fmt::Write as _, fs, path::Path};
+
+use toml::{Table, Value};
+
+const SECTIONS: &[&str] = &["product", "policy", "keys", "ds", "defaults"];
+
+/// Bake the bundle at `path`; key files are resolved relative to it.
+pub fn bake_file(path: &Path) -> Result<String, String> {
+    let text =
+        fs::read_to_string(path).map_err(|e| format!("read bundle {}: {e}", path.display()))?;
+    let base = path.parent().unwrap_or(Path::new("."));
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    bake(&text, base, &name)
+}
+
+/// Generated module for bundle `text`. Output depends only on the bundle contents, so it
+/// can be committed and checked for drift.
+pub fn bake(text: &str, base: &Path, source: &str) -> Result<String, String> {
+    let bundle: Table = text.parse().map_err(|e| format!("{source}: {e}"))?;
+    for key in bundle.keys() {
+        if !SECTIONS.contains(&key.as_str()) {
+            return Err(format!(
+                "{source}: unknown section `{key}` (expected one of: {})",
+                SECTIONS.join(", ")
+            ));
+        }
+    }
+
+    let mut body = String::new();
+
+    // 1) Product identity
+    let product = table(&bundle, "product")?;
+    for field in ["name", "version"] {
+        let v = match product.as_ref().and_then(|p| p.get(field)) {
+            Some(Value::String(s)) => s.clone(),
+            Some(_) => return Err(format!("product.{field}: expected a string")),
+            None => return Err(format!("{source}: product.{field} is required")),
+        };
+        let _ = writeln!(
+            body,
+            "pub const PRODUCT_{}: &str = {v:?};",
+            field.to_ascii_uppercase()
+        );
+    }
+    if let Some(extra) = product
+        .iter()
+        .flat_map(|p| p.keys())
+        .find(|k| !["name", "version"].contains(&k.as_str()))
+    {
+        return Err(format!("product.{extra}: unknown field"));
+    }
+
+    // 2) Scalar sections
+    for (section, prefix) in [("policy", "POLICY"), ("ds", "DS"), ("defaults", "DEFAULT")] {
+        let Some(t) = table(&bundle, section)? else {
+            continue;
+        };
+        body.push('\n');
+        let mut seen = BTreeSet::new();
+        for (key, value) in &t {
+            let ident = format!("{prefix}_{}", ident(section, key, &mut seen)?);
+            let (ty, lit) = match (section, value) {
+                ("ds", Value::String(s)) => ("&str", format!("{s:?}")),
+                ("ds", _) => return Err(format!("ds.{key}: domain separators are strings")),
+                (_, v) => scalar(section, key, v)?,
+            };
+            let _ = writeln!(body, "pub const {ident}: {ty} = {lit};");
+        }
+    }
+
+    // 3) Trusted keys
+    let keys = match bundle.get("keys") {
+        None => Vec::new(),
+        Some(Value::Array(a)) => a.clone(),
+        Some(_) => {
+            return Err(format!(
+                "{source}: `keys` must be an array of tables ([[keys]])"
+            ))
+        }
+    };
+    let mut seen = BTreeSet::new();
+    let mut names = Vec::new();
+    for (i, k) in keys.iter().enumerate() {
+        let Value::Table(k) = k else {
+            return Err(format!("keys[{i}]: expected a table"));
+        };
+        let name = match k.get("name") {
+            Some(Value::String(s)) => s.clone(),
+            _ => return Err(format!("keys[{i}]: `name` (string) is required")),
+        };
+        let key = key_bytes(k, i, base)?;
+        let ident = format!("KEY_{}_ED25519", ident("keys", &name, &mut seen)?);
+        body.push('\n');
+        let _ = writeln!(body, "pub const {ident}: [u8; 32] = {};", bytes(&key));
+        names.push((name, ident));
+    }
+    body.push('\n');
+    body.push_str("/// (name, Ed25519 public key) for every trusted key in the bundle\n");
+    body.push_str("pub const TRUSTED_KEYS: &[(&str, [u8; 32])] = &[\n");
+    for (name, ident) in &names {
+        let _ = writeln!(body, "    ({name:?}, {ident}),");
+    }
+    body.push_str("];\n");
+
+    let digest = blake3::hash(body.as_bytes());
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "// @generated by nonos-bakeconfig from {source}; do not edit."
+    );
+    out.push_str("// Regenerate: cargo run -p nonos-bakeconfig -- --bundle <bundle.toml> --out <this file>\n\n");
+    out.push_str(
+        "/// BLAKE3 of the baked contents below; identifies the bundle an image was built from\n",
+    );
+    let _ = writeln!(
+        out,
+        "pub const BUNDLE_BLAKE3: [u8; 32] = {};\n",
+        bytes(digest.as_bytes())
+    );
+    out.push_str(&body);
+    Ok(out)
+}
+
+/* ---------------- helpers ---------------- */
+
+fn table(bundle: &Table, name: &str) -> Result<Option<Table>, String> {
+    match bundle.get(name) {
+        None => Ok(None),
+        Some(Value::Table(t)) => Ok(Some(t.clone())),
+        Some(_) => Err(format!("`{name}` must be a table ([{name}])")),
+    }
+}
+
+/// Upper-case Rust identifier for `key`; rejects collisions after sanitizing.
+fn ident(section: &str, key: &str, seen: &mut BTreeSet<String>) -> Result<String, String> {
+    let id: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if id.is_empty() || !id.chars().any(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!("{section}.{key:?}: not usable as a name"));
+    }
+    if !seen.insert(id.clone()) {
+        return Err(format!(
+            "{section}.{key}: name collides with another entry as `{id}`"
+        ));
+    }
+    Ok(id)
+}
+
+fn scalar(section: &str, key: &str, v: &Value) -> Result<(&'static str, String), String> {
+    match v {
+        Value::Boolean(b) => Ok(("bool", b.to_string())),
+        Value::Integer(i) if *i >= 0 => Ok(("u64", i.to_string())),
+        Value::Integer(_) => Err(format!(
+            "{section}.{key}: negative integers are not supported"
+        )),
+        Value::String(s) => Ok(("&str", format!("{s:?}"))),
+        _ => Err(format!(
+            "{section}.{key}: expected bool, non-negative integer or string, got {}",
+            v.type_str()
+        )),
+    }
+}
+
+fn key_bytes(k: &Table, i: usize, base: &Path) -> Result<[u8; 32], String> {
+    let raw = match (k.get("ed25519"), k.get("file")) {
+        (Some(Value::String(h)), None) => {
+            let h = h.trim().trim_start_matches("0x").trim_start_matches("0X");
+            hex::decode(h).map_err(|e| format!("keys[{i}].ed25519: {e}"))?
+        }
+        (None, Some(Value::String(p))) => {
+            let path = base.join(p);
+            fs::read(&path).map_err(|e| format!("keys[{i}]: read {}: {e}", path.display()))?
+        }
+        _ => {
+            return Err(format!(
+                "keys[{i}]: provide exactly one of `ed25519` (hex) or `file`"
+            ))
+        }
+    };
+    if let Some(extra) = k
+        .keys()
+        .find(|f| !["name", "ed25519", "file"].contains(&f.as_str()))
+    {
+        return Err(format!("keys[{i}].{extra}: unknown field"));
+    }
+    raw.try_into().map_err(|r: Vec<u8>| {
+        format!(
+            "keys[{i}]: Ed25519 public keys are 32 bytes, got {}",
+            r.len()
+        )
+    })
+}
+
+fn bytes(b: &[u8]) -> String {
+    let mut out = String::from("[\n");
+    for line in b.chunks(16) {
+        let hex: Vec<String> = line.iter().map(|b| format!("0x{b:02x}")).collect();
+        let _ = writeln!(out, "    {},", hex.join(", "));
+    }
+    out.push(']');
+    out
+}
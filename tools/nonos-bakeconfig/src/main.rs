@@ -0,0 +1,62 @@
+//! NONOS nonos-bakeconfig — CLI front end (build-time)
+//!
+//! Author: eK (team@nonos.systems) — https://nonos.systems
+//! Purpose: `--bundle product.toml --out <module>`; `--check` fails when the committed
+//! module no longer matches its bundle.
+
+use std::{fs, path::PathBuf};
+
+use clap::Parser;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "nonos-bakeconfig",
+    version,
+    about = "NONOS nonos-bakeconfig — compile a deployment config bundle into a Rust module"
+)]
+struct Args {
+    /// Config bundle (TOML)
+    #[arg(long, value_name = "PATH")]
+    bundle: PathBuf,
+
+    /// Generated module (stdout if not set)
+    #[arg(long, value_name = "PATH")]
+    out: Option<PathBuf>,
+
+    /// Do not write; exit non-zero if --out differs from what the bundle produces
+    #[arg(long, requires = "out")]
+    check: bool,
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("[x] {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let args = Args::parse();
+    let module = nonos_bakeconfig::bake_file(&args.bundle)?;
+
+    match (&args.out, args.check) {
+        (Some(path), true) => {
+            let current = fs::read_to_string(path).unwrap_or_default();
+            if current != module {
+                return Err(format!(
+                    "{} is stale; regenerate it from {}",
+                    path.display(),
+                    args.bundle.display()
+                ));
+            }
+            println!("[+] {} matches {}", path.display(), args.bundle.display());
+        }
+        (Some(path), false) => {
+            fs::write(path, module.as_bytes())
+                .map_err(|e| format!("write {}: {}", path.display(), e))?;
+            println!("[+] wrote {}", path.display());
+        }
+        (None, _) => print!("{module}"),
+    }
+    Ok(())
+}
@@ -0,0 +1,154 @@
+//! UEFI boot entries through Linux efivarfs: EFI_LOAD_OPTION encoding, Boot#### / BootOrder.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::esp::Partition;
+
+/// EFI_GLOBAL_VARIABLE vendor GUID (Boot####, BootOrder)
+const GLOBAL_GUID: &str = "8be4df61-93ca-11d2-aa0d-00e098032b8c";
+
+/// NON_VOLATILE | BOOTSERVICE_ACCESS | RUNTIME_ACCESS
+const ATTRS: u32 = 0x7;
+
+const LOAD_OPTION_ACTIVE: u32 = 0x1;
+
+pub struct Efivars {
+    dir: PathBuf,
+}
+
+impl Efivars {
+    pub fn new(dir: &Path) -> Result<Self, String> {
+        if !dir.is_dir() {
+            return Err(format!(
+                "{} not found (booted without UEFI, or efivarfs not mounted)",
+                dir.display()
+            ));
+        }
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{name}-{GLOBAL_GUID}"))
+    }
+
+    /// Variable payload without the leading attribute word
+    fn read(&self, name: &str) -> Option<Vec<u8>> {
+        let raw = fs::read(self.path(name)).ok()?;
+        (raw.len() >= 4).then(|| raw[4..].to_vec())
+    }
+
+    fn write(&self, name: &str, data: &[u8]) -> Result<(), String> {
+        let path = self.path(name);
+        if path.exists() {
+            // efivarfs marks existing variables immutable; best effort, write reports failure
+            let _ = Command::new("chattr").arg("-i").arg(&path).status();
+        }
+        let mut buf = ATTRS.to_le_bytes().to_vec();
+        buf.extend_from_slice(data);
+        // efivarfs wants the whole variable in a single write
+        fs::write(&path, &buf).map_err(|e| format!("write {}: {e}", path.display()))
+    }
+
+    pub fn boot_order(&self) -> Vec<u16> {
+        self.read("BootOrder")
+            .unwrap_or_default()
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect()
+    }
+
+    pub fn set_boot_order(&self, order: &[u16]) -> Result<(), String> {
+        let data: Vec<u8> = order.iter().flat_map(|n| n.to_le_bytes()).collect();
+        self.write("BootOrder", &data)
+    }
+
+    /// Existing Boot#### numbers with their descriptions
+    pub fn boot_entries(&self) -> Vec<(u16, String)> {
+        let mut out = Vec::new();
+        let Ok(dir) = fs::read_dir(&self.dir) else {
+            return out;
+        };
+        let suffix = format!("-{GLOBAL_GUID}");
+        for entry in dir.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let Some(num) = name
+                .strip_suffix(&suffix)
+                .and_then(|n| n.strip_prefix("Boot"))
+                .filter(|n| n.len() == 4)
+                .and_then(|n| u16::from_str_radix(n, 16).ok())
+            else {
+                continue;
+            };
+            let desc = self
+                .read(&boot_var(num))
+                .map(|d| describe(&d))
+                .unwrap_or_default();
+            out.push((num, desc));
+        }
+        out.sort();
+        out
+    }
+
+    pub fn set_boot_entry(&self, num: u16, option: &[u8]) -> Result<(), String> {
+        self.write(&boot_var(num), option)
+    }
+}
+
+pub fn boot_var(num: u16) -> String {
+    format!("Boot{num:04X}")
+}
+
+/// EFI_LOAD_OPTION for `loader` (an ESP path like `\EFI\nonos\nonos_boot.efi`) on `part`.
+pub fn load_option(label: &str, part: &Partition, loader: &str) -> Vec<u8> {
+    let mut path = Vec::new();
+
+    // Hard drive media device path (type 4, subtype 1)
+    path.extend_from_slice(&[0x04, 0x01, 42, 0]);
+    path.extend_from_slice(&part.number.to_le_bytes());
+    path.extend_from_slice(&part.start_lba.to_le_bytes());
+    path.extend_from_slice(&part.size_lba.to_le_bytes());
+    path.extend_from_slice(&part.guid);
+    path.push(0x02); // partition format: GPT
+    path.push(0x02); // signature type: GUID
+
+    // File path media device path (type 4, subtype 4)
+    let file = utf16z(loader);
+    path.extend_from_slice(&[0x04, 0x04]);
+    path.extend_from_slice(&((4 + file.len()) as u16).to_le_bytes());
+    path.extend_from_slice(&file);
+
+    // End of entire device path
+    path.extend_from_slice(&[0x7f, 0xff, 0x04, 0x00]);
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&LOAD_OPTION_ACTIVE.to_le_bytes());
+    out.extend_from_slice(&(path.len() as u16).to_le_bytes());
+    out.extend_from_slice(&utf16z(label));
+    out.extend_from_slice(&path);
+    out
+}
+
+/// Description field of an EFI_LOAD_OPTION
+fn describe(option: &[u8]) -> String {
+    let units: Vec<u16> = option
+        .get(6..)
+        .unwrap_or_default()
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&u| u != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn utf16z(s: &str) -> Vec<u8> {
+    s.encode_utf16()
+        .chain(std::iter::once(0))
+        .flat_map(u16::to_le_bytes)
+        .collect()
+}
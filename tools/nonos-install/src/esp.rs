@@ -0,0 +1,186 @@
+//! ESP discovery (Linux): which GPT partition backs a mount point, in the terms a UEFI
+//! device path needs (partition number, LBA range, partition GUID).
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub struct Partition {
+    /// Block device backing the mount (e.g. /dev/nvme0n1p1), for display only
+    pub device: String,
+    pub number: u32,
+    pub start_lba: u64,
+    pub size_lba: u64,
+    /// Unique partition GUID in GPT on-disk byte order
+    pub guid: [u8; 16],
+}
+
+struct Mount {
+    dev_id: String,
+    source: String,
+    fstype: String,
+}
+
+/// Resolve the partition mounted at `esp`. `guid_override` skips the udev lookup.
+pub fn locate(esp: &Path, guid_override: Option<&str>) -> Result<Partition, String> {
+    let esp = fs::canonicalize(esp).map_err(|e| format!("resolve {}: {e}", esp.display()))?;
+    let mount = find_mount(&esp)?;
+    if mount.fstype != "vfat" {
+        return Err(format!(
+            "{} is {} ({}), not a FAT ESP",
+            esp.display(),
+            mount.source,
+            mount.fstype
+        ));
+    }
+
+    let sys = PathBuf::from("/sys/dev/block").join(&mount.dev_id);
+    let read_num = |name: &str| -> Result<u64, String> {
+        let p = sys.join(name);
+        fs::read_to_string(&p)
+            .map_err(|e| {
+                format!(
+                    "read {}: {e} (is {} a partition?)",
+                    p.display(),
+                    mount.source
+                )
+            })?
+            .trim()
+            .parse()
+            .map_err(|_| format!("unexpected contents in {}", p.display()))
+    };
+    let number = read_num("partition")? as u32;
+    // sysfs counts 512-byte sectors whatever the device's logical block size
+    let lbs = fs::read_to_string(sys.join("../queue/logical_block_size"))
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .unwrap_or(512);
+    let start_lba = read_num("start")? * 512 / lbs;
+    let size_lba = read_num("size")? * 512 / lbs;
+
+    let guid_text = match guid_override {
+        Some(g) => g.to_string(),
+        None => udev_part_uuid(&mount.dev_id).ok_or_else(|| {
+            format!(
+                "no GPT partition GUID for {} in udev data; pass --part-guid",
+                mount.source
+            )
+        })?,
+    };
+
+    Ok(Partition {
+        device: mount.source,
+        number,
+        start_lba,
+        size_lba,
+        guid: parse_guid(&guid_text)?,
+    })
+}
+
+/// Longest mount point in /proc/self/mountinfo that contains `path`.
+fn find_mount(path: &Path) -> Result<Mount, String> {
+    let info = fs::read_to_string("/proc/self/mountinfo")
+        .map_err(|e| format!("read /proc/self/mountinfo: {e}"))?;
+    let mut best: Option<(usize, Mount)> = None;
+    for line in info.lines() {
+        // id parent maj:min root mountpoint opts [optional...] - fstype source superopts
+        let Some((pre, post)) = line.split_once(" - ") else {
+            continue;
+        };
+        let pre: Vec<&str> = pre.split(' ').collect();
+        let post: Vec<&str> = post.split(' ').collect();
+        if pre.len() < 5 || post.len() < 2 {
+            continue;
+        }
+        let point = PathBuf::from(unescape(pre[4]));
+        if !path.starts_with(&point) {
+            continue;
+        }
+        let depth = point.components().count();
+        if best.as_ref().is_some_and(|(d, _)| *d > depth) {
+            continue;
+        }
+        best = Some((
+            depth,
+            Mount {
+                dev_id: pre[2].to_string(),
+                fstype: post[0].to_string(),
+                source: unescape(post[1]),
+            },
+        ));
+    }
+    best.map(|(_, m)| m)
+        .ok_or_else(|| format!("{} is not on any mounted filesystem", path.display()))
+}
+
+/// mountinfo escapes space, tab, newline and backslash as `\ooo`
+fn unescape(s: &str) -> String {
+    let b = s.as_bytes();
+    let mut out = Vec::with_capacity(b.len());
+    let mut i = 0;
+    while i < b.len() {
+        if b[i] == b'\\'
+            && i + 3 < b.len()
+            && b[i + 1..i + 4].iter().all(|c| (b'0'..=b'7').contains(c))
+        {
+            out.push((b[i + 1] - b'0') * 64 + (b[i + 2] - b'0') * 8 + (b[i + 3] - b'0'));
+            i += 4;
+        } else {
+            out.push(b[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn udev_part_uuid(dev_id: &str) -> Option<String> {
+    let data = fs::read_to_string(format!("/run/udev/data/b{dev_id}")).ok()?;
+    let mut scheme = None;
+    let mut uuid = None;
+    for line in data.lines() {
+        if let Some(v) = line.strip_prefix("E:ID_PART_ENTRY_SCHEME=") {
+            scheme = Some(v.to_string());
+        }
+        if let Some(v) = line.strip_prefix("E:ID_PART_ENTRY_UUID=") {
+            uuid = Some(v.to_string());
+        }
+    }
+    match scheme.as_deref() {
+        Some("gpt") => uuid,
+        _ => None,
+    }
+}
+
+/// `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx` → GPT byte order (first three fields little-endian)
+pub fn parse_guid(s: &str) -> Result<[u8; 16], String> {
+    let bad = || format!("bad GUID `{s}`");
+    let parts: Vec<&str> = s.trim().split('-').collect();
+    if parts.len() != 5 || [8, 4, 4, 4, 12] != parts.iter().map(|p| p.len()).collect::<Vec<_>>()[..]
+    {
+        return Err(bad());
+    }
+    let bytes = hex::decode(parts.concat()).map_err(|_| bad())?;
+    let mut out = [0u8; 16];
+    out.copy_from_slice(&bytes);
+    out[0..4].reverse();
+    out[4..6].reverse();
+    out[6..8].reverse();
+    Ok(out)
+}
+
+pub fn format_guid(g: &[u8; 16]) -> String {
+    let mut b = *g;
+    b[0..4].reverse();
+    b[4..6].reverse();
+    b[6..8].reverse();
+    let h = hex::encode(b);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &h[0..8],
+        &h[8..12],
+        &h[12..16],
+        &h[16..20],
+        &h[20..]
+    )
+}
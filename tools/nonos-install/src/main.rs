@@ -0,0 +1,401 @@
+//! NONOS nonos-install — put the bootloader on an ESP and register it with firmware (host-side)
+//!
+//! Author: eK (team@nonos.systems) — https://nonos.systems
+//! Purpose: replace the manual install steps with one command that validates its inputs,
+//! prints exactly what it will do (`--dry-run`), and is safe to re-run.
+
+mod efivars;
+mod esp;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::{Parser, ValueEnum};
+
+use efivars::Efivars;
+use esp::Partition;
+
+/// Install locations inside the ESP (same layout as `cargo xtask test-boot` / `image`)
+const ESP_LOADER: &str = "EFI/nonos/nonos_boot.efi";
+const ESP_CAPSULE: &str = "EFI/nonos/capsule.caps";
+const ESP_CONFIG: &str = "EFI/nonos/boot.cfg";
+
+const EFIVARS_DEFAULT: &str = "/sys/firmware/efi/efivars";
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Arch {
+    #[value(name = "x86_64")]
+    X86_64,
+    #[value(name = "aarch64")]
+    Aarch64,
+}
+
+impl Arch {
+    /// PE/COFF machine type the loader must carry
+    fn pe_machine(self) -> u16 {
+        match self {
+            Arch::X86_64 => 0x8664,
+            Arch::Aarch64 => 0xaa64,
+        }
+    }
+
+    fn removable_path(self) -> &'static str {
+        match self {
+            Arch::X86_64 => "EFI/BOOT/BOOTX64.EFI",
+            Arch::Aarch64 => "EFI/BOOT/BOOTAA64.EFI",
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "nonos-install",
+    version,
+    about = "NONOS nonos-install — install the bootloader to an ESP and create its UEFI boot entry"
+)]
+struct Args {
+    /// Mounted EFI System Partition
+    #[arg(long, value_name = "DIR", default_value = "/boot/efi")]
+    esp: PathBuf,
+
+    /// Bootloader EFI binary (e.g. target/x86_64-unknown-uefi/release/nonos_boot.efi)
+    #[arg(long, value_name = "PATH")]
+    loader: PathBuf,
+
+    /// Architecture the loader must be built for
+    #[arg(long, value_enum, default_value = "x86_64")]
+    arch: Arch,
+
+    /// Boot config written to EFI/nonos/boot.cfg
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Capsule copied to EFI/nonos/capsule.caps
+    #[arg(long, value_name = "PATH")]
+    capsule: Option<PathBuf>,
+
+    /// Expected BLAKE3 of the capsule (hex); install aborts on mismatch
+    #[arg(long, value_name = "HEX", requires = "capsule")]
+    capsule_blake3: Option<String>,
+
+    /// Also install to the removable-media path (EFI/BOOT/BOOT<ARCH>.EFI)
+    #[arg(long)]
+    removable: bool,
+
+    /// With --removable, replace a different loader already at that path (kept as .bak)
+    #[arg(long, requires = "removable")]
+    force: bool,
+
+    /// Boot entry description shown in the firmware boot menu
+    #[arg(long, value_name = "STR", default_value = "NONOS")]
+    label: String,
+
+    /// Copy files only; leave firmware boot entries alone
+    #[arg(long)]
+    no_boot_entry: bool,
+
+    /// Create the entry but do not move it to the front of BootOrder
+    #[arg(long)]
+    no_boot_order: bool,
+
+    /// Unique partition GUID of the ESP, when udev cannot tell us
+    #[arg(long, value_name = "GUID")]
+    part_guid: Option<String>,
+
+    /// efivarfs mount point
+    #[arg(long, value_name = "DIR", default_value = EFIVARS_DEFAULT)]
+    efivars: PathBuf,
+
+    /// Print the plan and exit without changing anything
+    #[arg(long)]
+    dry_run: bool,
+}
+
+/// One action of the install plan
+enum Step {
+    Copy {
+        what: &'static str,
+        src: PathBuf,
+        dest: PathBuf,
+        blake3: String,
+        /// dest already holds identical bytes
+        unchanged: bool,
+    },
+    BootEntry {
+        num: u16,
+        replaces: bool,
+        option: Vec<u8>,
+    },
+    BootOrder {
+        order: Vec<u16>,
+    },
+    /// Move a foreign file aside before it is overwritten
+    Backup {
+        path: PathBuf,
+        to: PathBuf,
+    },
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("[x] {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let args = Args::parse();
+
+    // 1) Validate inputs before touching anything
+    if !args.esp.is_dir() {
+        return Err(format!("ESP {} is not a directory", args.esp.display()));
+    }
+    check_loader(&args.loader, args.arch)?;
+    if let Some(p) = &args.config {
+        let cfg = read(p, "config")?;
+        if cfg.is_empty() {
+            return Err(format!("config {} is empty", p.display()));
+        }
+    }
+    if let Some(p) = &args.capsule {
+        check_capsule(p, args.capsule_blake3.as_deref())?;
+    }
+
+    // 2) Plan
+    let mut plan = Vec::new();
+    plan.push(copy_step(
+        "loader",
+        &args.loader,
+        &args.esp.join(ESP_LOADER),
+    )?);
+    if args.removable {
+        // The fallback path usually belongs to another OS's loader (shim, Windows boot manager)
+        let dest = args.esp.join(args.arch.removable_path());
+        let step = copy_step("loader", &args.loader, &dest)?;
+        if matches!(
+            step,
+            Step::Copy {
+                unchanged: false,
+                ..
+            }
+        ) && dest.exists()
+        {
+            if !args.force {
+                return Err(format!(
+                    "{} already holds another loader; pass --force to replace it (kept as .bak)",
+                    dest.display()
+                ));
+            }
+            // An existing .bak is the original from an earlier --force; never clobber it
+            let to = dest.with_extension("EFI.bak");
+            if !to.exists() {
+                plan.push(Step::Backup { path: dest, to });
+            }
+        }
+        plan.push(step);
+    }
+    if let Some(p) = &args.config {
+        plan.push(copy_step("config", p, &args.esp.join(ESP_CONFIG))?);
+    }
+    if let Some(p) = &args.capsule {
+        plan.push(copy_step("capsule", p, &args.esp.join(ESP_CAPSULE))?);
+    }
+
+    let mut part = None;
+    let vars = if args.no_boot_entry {
+        None
+    } else {
+        let p = esp::locate(&args.esp, args.part_guid.as_deref())?;
+        let vars = Efivars::new(&args.efivars)?;
+        plan_boot_entry(&args, &vars, &p, &mut plan);
+        part = Some(p);
+        Some(vars)
+    };
+
+    // 3) Show it
+    println!("[*] install plan for {}", args.esp.display());
+    if let Some(p) = &part {
+        println!(
+            "    ESP: {} (partition {}, LBA {}+{}, GUID {})",
+            p.device,
+            p.number,
+            p.start_lba,
+            p.size_lba,
+            esp::format_guid(&p.guid)
+        );
+    }
+    for step in &plan {
+        println!("    {}", describe(step));
+    }
+    if args.dry_run {
+        println!("[+] dry run: nothing changed");
+        return Ok(());
+    }
+
+    // 4) Apply
+    for step in &plan {
+        match step {
+            Step::Copy {
+                unchanged: true, ..
+            } => {}
+            Step::Copy { src, dest, .. } => install_file(src, dest)?,
+            Step::Backup { path, to } => fs::rename(path, to)
+                .map_err(|e| format!("rename {} -> {}: {e}", path.display(), to.display()))?,
+            Step::BootEntry { num, option, .. } => vars
+                .as_ref()
+                .ok_or("boot entry planned without efivars")?
+                .set_boot_entry(*num, option)?,
+            Step::BootOrder { order } => vars
+                .as_ref()
+                .ok_or("boot order planned without efivars")?
+                .set_boot_order(order)?,
+        }
+    }
+    println!("[+] installed");
+    Ok(())
+}
+
+/* ---------------- helpers ---------------- */
+
+fn read(path: &Path, what: &str) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|e| format!("read {what} {}: {e}", path.display()))
+}
+
+/// PE32+ EFI application for the requested machine
+fn check_loader(path: &Path, arch: Arch) -> Result<(), String> {
+    let b = read(path, "loader")?;
+    let bad = |why: &str| Err(format!("loader {}: {why}", path.display()));
+    if b.len() < 0x40 || &b[..2] != b"MZ" {
+        return bad("not a PE image (no MZ header)");
+    }
+    let pe = u32::from_le_bytes([b[0x3c], b[0x3d], b[0x3e], b[0x3f]]) as usize;
+    // PE sig (4) + COFF header (20) + optional header up to Subsystem (70)
+    if b.len() < pe + 24 + 70 || &b[pe..pe + 4] != b"PE\0\0" {
+        return bad("truncated or missing PE header");
+    }
+    let machine = u16::from_le_bytes([b[pe + 4], b[pe + 5]]);
+    if machine != arch.pe_machine() {
+        return bad(&format!(
+            "machine {machine:#06x}, expected {:#06x} for {arch:?}",
+            arch.pe_machine()
+        ));
+    }
+    let opt = pe + 24;
+    if u16::from_le_bytes([b[opt], b[opt + 1]]) != 0x20b {
+        return bad("not PE32+");
+    }
+    let subsystem = u16::from_le_bytes([b[opt + 68], b[opt + 69]]);
+    if subsystem != 10 {
+        return bad(&format!(
+            "subsystem {subsystem}, expected 10 (EFI application)"
+        ));
+    }
+    Ok(())
+}
+
+/// Structural capsule checks happen in the loader; here we only refuse obviously wrong files.
+fn check_capsule(path: &Path, expected: Option<&str>) -> Result<(), String> {
+    let b = read(path, "capsule")?;
+    if b.is_empty() {
+        return Err(format!("capsule {} is empty", path.display()));
+    }
+    if let Some(want) = expected {
+        let want = want.trim().to_ascii_lowercase();
+        let got = blake3::hash(&b).to_hex().to_string();
+        if got != want {
+            return Err(format!(
+                "capsule {} has BLAKE3 {got}, expected {want}",
+                path.display()
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn copy_step(what: &'static str, src: &Path, dest: &Path) -> Result<Step, String> {
+    let bytes = read(src, what)?;
+    let unchanged = fs::read(dest).map(|d| d == bytes).unwrap_or(false);
+    Ok(Step::Copy {
+        what,
+        src: src.to_path_buf(),
+        dest: dest.to_path_buf(),
+        blake3: blake3::hash(&bytes).to_hex().to_string(),
+        unchanged,
+    })
+}
+
+/// Reuse an entry with our label (re-install), otherwise take the lowest free Boot####.
+fn plan_boot_entry(args: &Args, vars: &Efivars, part: &Partition, plan: &mut Vec<Step>) {
+    let entries = vars.boot_entries();
+    let existing = entries
+        .iter()
+        .find(|(_, d)| d == &args.label)
+        .map(|(n, _)| *n);
+    let num = existing.unwrap_or_else(|| {
+        (0..=u16::MAX)
+            .find(|n| !entries.iter().any(|(e, _)| e == n))
+            .unwrap_or(u16::MAX)
+    });
+
+    let loader = format!("\\{}", ESP_LOADER.replace('/', "\\"));
+    plan.push(Step::BootEntry {
+        num,
+        replaces: existing.is_some(),
+        option: efivars::load_option(&args.label, part, &loader),
+    });
+
+    if !args.no_boot_order {
+        let mut order = vars.boot_order();
+        if order.first() != Some(&num) {
+            order.retain(|n| *n != num);
+            order.insert(0, num);
+            plan.push(Step::BootOrder { order });
+        }
+    }
+}
+
+fn describe(step: &Step) -> String {
+    match step {
+        Step::Copy {
+            what,
+            src,
+            dest,
+            blake3,
+            unchanged,
+        } => format!(
+            "{} {what:<8} {} -> {} (blake3 {})",
+            if *unchanged { "keep   " } else { "copy   " },
+            src.display(),
+            dest.display(),
+            &blake3[..16]
+        ),
+        Step::BootEntry { num, replaces, .. } => format!(
+            "{} {} -> \\{}",
+            if *replaces { "update " } else { "create " },
+            efivars::boot_var(*num),
+            ESP_LOADER.replace('/', "\\")
+        ),
+        Step::Backup { path, to } => format!("backup  {} -> {}", path.display(), to.display()),
+        Step::BootOrder { order } => format!(
+            "order   BootOrder = {}",
+            order
+                .iter()
+                .map(|n| format!("{n:04X}"))
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+    }
+}
+
+/// Write next to the destination and rename, so a failed copy never leaves half a loader.
+fn install_file(src: &Path, dest: &Path) -> Result<(), String> {
+    if let Some(dir) = dest.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("create {}: {e}", dir.display()))?;
+    }
+    let tmp = dest.with_extension("new");
+    fs::copy(src, &tmp).map_err(|e| format!("copy {} -> {}: {e}", src.display(), tmp.display()))?;
+    fs::rename(&tmp, dest).map_err(|e| format!("rename {}: {e}", dest.display()))?;
+    Ok(())
+}
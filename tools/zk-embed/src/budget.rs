@@ -0,0 +1,58 @@
+//! `--size-report` / `--max-bytes`: what the registry costs in flash, on stderr so it never
+//! mixes with the generated output.
+//!
+//! "Embedded" counts what lands in the image: PROGRAM_HASH (32), the compressed VK, and
+//! with `--sign-key` the 64-byte signature per program (the signer key is pinned elsewhere).
+
+use crate::Entry;
+
+pub fn report(entries: &[Entry], signed: bool, show: bool, max_bytes: Option<usize>) {
+    let per_entry = |e: &Entry| 32 + e.vk.len() + if signed { 64 } else { 0 };
+    let total: usize = entries.iter().map(per_entry).sum();
+
+    if show {
+        for e in entries {
+            let s = &e.stats;
+            eprintln!(
+                "[*] {} ({}): VK {} bytes compressed, {} uncompressed (saves {}); {} IC points ({} public inputs); embeds {} bytes",
+                e.prefix,
+                e.curve.name(),
+                e.vk.len(),
+                s.uncompressed_len,
+                s.uncompressed_len - e.vk.len(),
+                s.ic_points,
+                s.ic_points.saturating_sub(1),
+                per_entry(e)
+            );
+        }
+        eprintln!("[*] total: {} program(s), {total} bytes embedded", entries.len());
+    }
+
+    let Some(max) = max_bytes else {
+        return;
+    };
+    if total <= max {
+        eprintln!("[+] {total} bytes embedded, within --max-bytes {max} ({} to spare)", max - total);
+        return;
+    }
+    let over = total - max;
+    eprintln!("[-] {total} bytes embedded, {over} over --max-bytes {max}");
+    eprintln!("[-] each public input adds one compressed G1 point to its VK");
+
+    // The one program that could absorb the whole overshoot with the fewest dropped inputs
+    let fit = entries
+        .iter()
+        .map(|e| (e, over.div_ceil(e.stats.g1_compressed_len)))
+        .filter(|(e, n)| *n <= e.stats.ic_points.saturating_sub(1))
+        .min_by_key(|(_, n)| *n);
+    match fit {
+        Some((e, n)) => eprintln!(
+            "[-] {n} fewer public inputs on {} ({} bytes each on {}) would fit; \
+             hash several inputs into one, or move fixed values into the circuit",
+            e.prefix,
+            e.stats.g1_compressed_len,
+            e.curve.name()
+        ),
+        None => eprintln!("[-] fewer public inputs on one program cannot close the gap; split or drop programs"),
+    }
+}
@@ -0,0 +1,85 @@
+//! `zk-embed check`: find the embedded constants in a source file and compare them with a
+//! fresh derivation, so CI notices a key or program ID that changed without a regenerate.
+//!
+//! Only the initializer is parsed: `const NAME ... = [..]` / `&[..]` in Rust, or
+//! `NAME[N] = {..}` in a `--lang c` header. Elements are `0x..` or decimal bytes.
+
+use std::{fs, path::Path};
+
+/// Compare every `(name, expected)` pair against `source`; `Err` lists each drifted constant.
+pub fn audit(source: &Path, expected: &[(String, &[u8])]) -> Result<(), String> {
+    let text = fs::read_to_string(source).map_err(|e| format!("read {}: {e}", source.display()))?;
+
+    let mut drift = 0;
+    for (name, want) in expected {
+        match find_bytes(&text, name)? {
+            None => {
+                println!("[-] {name}: not found in {}", source.display());
+                drift += 1;
+            }
+            Some(have) if have == *want => println!("[+] {name}: up to date ({} bytes)", have.len()),
+            Some(have) => {
+                let at = have.iter().zip(want.iter()).position(|(a, b)| a != b).unwrap_or(have.len().min(want.len()));
+                println!("[-] {name}: stale ({} bytes embedded, {} derived, first difference at byte {at})", have.len(), want.len());
+                drift += 1;
+            }
+        }
+    }
+    if drift > 0 {
+        return Err(format!(
+            "{drift} of {} constants in {} do not match; regenerate them with zk-embed",
+            expected.len(),
+            source.display()
+        ));
+    }
+    Ok(())
+}
+
+/* ---------------- helpers ---------------- */
+
+/// Bytes of the array initializer for `name`, or `None` if no such definition exists
+fn find_bytes(text: &str, name: &str) -> Result<Option<Vec<u8>>, String> {
+    let Some(start) = find_definition(text, name) else {
+        return Ok(None);
+    };
+    let rest = &text[start..];
+    let eq = rest.find('=').ok_or_else(|| format!("{name}: no initializer"))?;
+    let body = &rest[eq + 1..];
+    let open = body.find(['[', '{']).ok_or_else(|| format!("{name}: initializer is not an array"))?;
+    let close = if body.as_bytes()[open] == b'[' { ']' } else { '}' };
+    let end = body[open..].find(close).ok_or_else(|| format!("{name}: unterminated array"))?;
+
+    let mut out = Vec::new();
+    for line in body[open + 1..open + end].lines() {
+        let line = line.split("//").next().unwrap_or_default();
+        for tok in line.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+            let parsed = match tok.strip_prefix("0x").or_else(|| tok.strip_prefix("0X")) {
+                Some(h) => u8::from_str_radix(h, 16),
+                None => tok.parse::<u8>(),
+            };
+            out.push(parsed.map_err(|_| format!("{name}: cannot parse `{tok}` as a byte"))?);
+        }
+    }
+    Ok(Some(out))
+}
+
+/// Offset just past `name` in its definition. The name must be followed by `:` (Rust) or
+/// `[` (C), so `PROGRAM_HASH_A` does not match `PROGRAM_HASH_A_BN` or a use in another const.
+fn find_definition(text: &str, name: &str) -> Option<usize> {
+    let mut from = 0;
+    while let Some(i) = text[from..].find(name) {
+        let at = from + i;
+        let end = at + name.len();
+        let before_ok = at == 0 || !is_ident(text.as_bytes()[at - 1]);
+        let after = text[end..].trim_start();
+        if before_ok && (after.starts_with(':') || after.starts_with('[')) {
+            return Some(end);
+        }
+        from = end;
+    }
+    None
+}
+
+fn is_ident(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
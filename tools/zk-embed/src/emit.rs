@@ -0,0 +1,190 @@
+//! Machine-readable outputs (`--format json|cbor`) for build scripts and CI.
+//!
+//! Both carry the same fields in the same order. JSON encodes byte fields as lowercase hex;
+//! CBOR (RFC 8949, definite lengths) uses byte strings. With `--manifest` the document is
+//! an array of these maps.
+
+use serde_json::{Map, Value};
+
+use crate::Curve;
+
+/// Everything zk-embed derived for one key
+pub struct Embedding<'a> {
+    pub prefix: &'a str,
+    pub curve: Curve,
+    pub ds: &'a str,
+    pub program_id: &'a [u8],
+    pub program_hash: &'a [u8; 32],
+    pub vk: &'a [u8],
+}
+
+enum Field<'a> {
+    Text(&'a str),
+    Bytes(&'a [u8]),
+}
+
+impl Embedding<'_> {
+    fn fields(&self) -> [(&'static str, Field<'_>); 8] {
+        [
+            ("prefix", Field::Text(self.prefix)),
+            ("scheme", Field::Text("groth16")),
+            ("curve", Field::Text(self.curve.name())),
+            ("compression", Field::Text("compressed")),
+            ("ds", Field::Text(self.ds)),
+            ("program_id", Field::Bytes(self.program_id)),
+            ("program_hash", Field::Bytes(self.program_hash)),
+            ("vk", Field::Bytes(self.vk)),
+        ]
+    }
+}
+
+pub fn json(e: &Embedding) -> String {
+    pretty(&json_value(e))
+}
+
+/// Keys stay in `fields()` order (serde_json `preserve_order`), the same order as CBOR
+fn json_value(e: &Embedding) -> Value {
+    let map: Map<String, Value> = e
+        .fields()
+        .into_iter()
+        .map(|(k, v)| {
+            let v = match v {
+                Field::Text(s) => Value::from(s),
+                Field::Bytes(b) => Value::from(hex::encode(b)),
+            };
+            (k.to_string(), v)
+        })
+        .collect();
+    Value::Object(map)
+}
+
+pub fn cbor(e: &Embedding) -> Vec<u8> {
+    let fields = e.fields();
+    let mut out = Vec::new();
+    cbor_head(&mut out, 5, fields.len() as u64);
+    for (k, v) in &fields {
+        cbor_head(&mut out, 3, k.len() as u64);
+        out.extend_from_slice(k.as_bytes());
+        match v {
+            Field::Text(s) => {
+                cbor_head(&mut out, 3, s.len() as u64);
+                out.extend_from_slice(s.as_bytes());
+            }
+            Field::Bytes(b) => {
+                cbor_head(&mut out, 2, b.len() as u64);
+                out.extend_from_slice(b);
+            }
+        }
+    }
+    out
+}
+
+/// `--manifest` mode: one object per program, in manifest order
+pub fn json_list(list: &[Embedding]) -> String {
+    pretty(&Value::Array(list.iter().map(json_value).collect()))
+}
+
+pub fn cbor_list(list: &[Embedding]) -> Vec<u8> {
+    let mut out = Vec::new();
+    cbor_head(&mut out, 4, list.len() as u64);
+    for e in list {
+        out.extend_from_slice(&cbor(e));
+    }
+    out
+}
+
+/// Blob magic ("NONOS ZK Blob") and layout version
+pub const BLOB_MAGIC: [u8; 4] = *b"NZKB";
+const BLOB_VERSION: u16 = 1;
+
+/// PE section the shim places the blob in (PE/COFF names are at most 8 bytes)
+const BLOB_SECTION: &str = ".zkreg";
+
+/// `--emit blob`: the registry as one binary, all integers little-endian.
+///
+/// ```text
+/// magic "NZKB" | version u16 | count u16 | ds_len u16 | ds
+/// count x ( curve u8 | scheme u8 | reserved u16 | program_hash [32] | vk_len u32 | vk )
+/// blake3 [32] over everything before it
+/// ```
+///
+/// `reserve` zero-pads the blob to a fixed size so a later key can be patched into the
+/// same section without relinking; readers stop at the trailing hash.
+pub fn blob(list: &[Embedding], reserve: Option<usize>) -> Result<Vec<u8>, String> {
+    let ds = list.first().map(|e| e.ds).unwrap_or_default();
+    let mut out = Vec::new();
+    out.extend_from_slice(&BLOB_MAGIC);
+    out.extend_from_slice(&BLOB_VERSION.to_le_bytes());
+    out.extend_from_slice(&u16::try_from(list.len()).map_err(|_| "too many programs for one blob")?.to_le_bytes());
+    out.extend_from_slice(&u16::try_from(ds.len()).map_err(|_| "domain separator too long")?.to_le_bytes());
+    out.extend_from_slice(ds.as_bytes());
+    for e in list {
+        out.push(curve_id(e.curve));
+        out.push(1); // scheme: groth16
+        out.extend_from_slice(&[0, 0]);
+        out.extend_from_slice(e.program_hash);
+        out.extend_from_slice(&u32::try_from(e.vk.len()).map_err(|_| "VK too large")?.to_le_bytes());
+        out.extend_from_slice(e.vk);
+    }
+    let digest = blake3::hash(&out);
+    out.extend_from_slice(digest.as_bytes());
+
+    if let Some(size) = reserve {
+        if out.len() > size {
+            return Err(format!("blob is {} bytes, over --blob-reserve {size}", out.len()));
+        }
+        out.resize(size, 0);
+    }
+    Ok(out)
+}
+
+/// Rust shim that links `blob_file` into its own section instead of pasting hex
+pub fn blob_shim(blob_file: &str, blob: &[u8]) -> String {
+    let mut out = String::new();
+    out.push_str("// --- zk-embed blob shim: add to src/zk/ next to the blob file ---\n");
+    out.push_str(&format!("// Blob: {blob_file} ({} bytes, blake3 {})\n", blob.len(), blake3::hash(blob).to_hex()));
+    out.push_str(&format!("// Swap keys post-build: objcopy --update-section {BLOB_SECTION}=<new blob> (same size; see --blob-reserve)\n\n"));
+    out.push_str(&format!("pub const ZK_BLOB_MAGIC: [u8; 4] = *b\"{}\";\n\n", String::from_utf8_lossy(&BLOB_MAGIC)));
+    out.push_str("#[used]\n");
+    out.push_str(&format!("#[link_section = \"{BLOB_SECTION}\"]\n"));
+    out.push_str(&format!("pub static ZK_REGISTRY_BLOB: [u8; {}] = *include_bytes!({blob_file:?});\n", blob.len()));
+    out
+}
+
+/* ---------------- helpers ---------------- */
+
+/// Curve byte of a blob entry, shared with the `gen-kat` vectors
+pub fn curve_id(curve: Curve) -> u8 {
+    match curve {
+        Curve::Bls12_381 => 1,
+        Curve::Bn254 => 2,
+    }
+}
+
+fn pretty(v: &Value) -> String {
+    // Serializing a Value cannot fail: every key is a string
+    let mut out = serde_json::to_string_pretty(v).unwrap_or_default();
+    out.push('\n');
+    out
+}
+
+/// Major type + argument in the shortest form
+fn cbor_head(out: &mut Vec<u8>, major: u8, n: u64) {
+    let m = major << 5;
+    match n {
+        0..=23 => out.push(m | n as u8),
+        24..=0xff => out.extend_from_slice(&[m | 24, n as u8]),
+        0x100..=0xffff => {
+            out.push(m | 25);
+            out.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(m | 26);
+            out.extend_from_slice(&(n as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(m | 27);
+            out.extend_from_slice(&n.to_be_bytes());
+        }
+    }
+}
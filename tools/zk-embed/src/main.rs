@@ -3,22 +3,113 @@
 //! Author: eK (team@nonos.systems) — https://nonos.systems
 //! Purpose: make it trivial to embed the right verifying key and program hash in the bootloader.
 
-use std::{fs, path::PathBuf};
+mod budget;
+mod check;
+mod emit;
+mod manifest;
+mod selftest;
+mod snarkjs;
 
-use blake3;
-use clap::Parser;
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
 
 use ark_bls12_381::Bls12_381;
-use ark_groth16::VerifyingKey;
+use ark_bn254::Bn254;
+use ark_ec::pairing::Pairing;
+use ark_ff::One;
+use ark_groth16::{Groth16, Proof, VerifyingKey};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
 use ark_std::io::Cursor;
+use ed25519_dalek::{pkcs8::DecodePrivateKey, Signer, SigningKey};
+use rayon::prelude::*;
 
 /// Domain separator for PROGRAM_HASH derivation
 const DS_PROGRAM_DEFAULT: &str = "NONOS:ZK:PROGRAM:v1";
 
+/// Pairing engine the verifying key is defined over
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Curve {
+    #[value(name = "bls12-381")]
+    Bls12_381,
+    #[value(name = "bn254")]
+    Bn254,
+}
+
+impl Curve {
+    /// Tag used in generated const names
+    fn tag(self) -> &'static str {
+        match self {
+            Curve::Bls12_381 => "BLS12_381",
+            Curve::Bn254 => "BN254",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Curve::Bls12_381 => "bls12-381",
+            Curve::Bn254 => "bn254",
+        }
+    }
+
+    /// Variant of the generated `Curve` enum
+    fn variant(self) -> &'static str {
+        match self {
+            Curve::Bls12_381 => "Bls12_381",
+            Curve::Bn254 => "Bn254",
+        }
+    }
+}
+
+/// Encoding of the `--vk` file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum VkFormat {
+    /// arkworks CanonicalSerialize (compressed or uncompressed)
+    Arkworks,
+    /// circom/snarkjs `verification_key.json`
+    SnarkjsJson,
+}
+
+/// Output document
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    /// Paste-ready Rust consts and lookup snippet
+    Rust,
+    /// JSON object (byte fields as hex)
+    Json,
+    /// CBOR map (byte fields as byte strings)
+    Cbor,
+}
+
+/// Language of `--format rust` source output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Lang {
+    /// Rust consts and lookup function
+    Rust,
+    /// C header with `static const uint8_t` arrays and the same names
+    C,
+}
+
+/// What to produce
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Emit {
+    /// Constants as source text (see --format)
+    Source,
+    /// Binary blob at --out plus an include_bytes!/link_section shim on stdout
+    Blob,
+}
+
 #[derive(Debug, Parser)]
 #[command(name = "zk-embed", version, about = "NONOS zk-embed — derive PROGRAM_HASH and emit Groth16 VK bytes")]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    cmd: Option<Cmd>,
+
     /// Program/circuit ID as UTF-8 string
     #[arg(long, value_name = "STR", group = "pid")]
     program_id_str: Option<String>,
@@ -31,9 +122,21 @@ struct Args {
     #[arg(long, value_name = "PATH", group = "pid")]
     program_id_file: Option<PathBuf>,
 
-    /// Verifying key file (arkworks CanonicalSerialize; compressed or uncompressed)
-    #[arg(long = "vk", value_name = "PATH")]
-    vk_path: PathBuf,
+    /// Verifying key file (arkworks CanonicalSerialize; compressed or uncompressed); `-` reads stdin
+    #[arg(long = "vk", value_name = "PATH", required_unless_present = "manifest")]
+    vk_path: Option<PathBuf>,
+
+    /// Registry manifest (TOML) listing every program; emits one complete registry
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["pid", "vk_path", "vk_format", "const_prefix", "curve"])]
+    manifest: Option<PathBuf>,
+
+    /// Encoding of the --vk file
+    #[arg(long, value_enum, default_value = "arkworks")]
+    vk_format: VkFormat,
+
+    /// Curve of the verifying key (tags the VK const name)
+    #[arg(long, value_enum, default_value = "bls12-381")]
+    curve: Curve,
 
     /// Prefix for generated const names (e.g., ATTEST_V1)
     #[arg(long, value_name = "NAME", default_value = "PROGRAM")]
@@ -43,11 +146,132 @@ struct Args {
     #[arg(long, value_name = "STR", default_value = DS_PROGRAM_DEFAULT)]
     ds_program: String,
 
-    /// Optional path to write the generated snippet (stdout if not set)
+    /// Output format
+    #[arg(long, value_enum, default_value = "rust")]
+    format: Format,
+
+    /// Source language for the constants (C for the recovery stage)
+    #[arg(long, value_enum, default_value = "rust")]
+    lang: Lang,
+
+    /// Source constants, or a binary blob plus a small linker-section shim
+    #[arg(long, value_enum, default_value = "source")]
+    emit: Emit,
+
+    /// Zero-pad the blob to this many bytes, leaving room to swap in larger keys later
+    #[arg(long, value_name = "BYTES")]
+    blob_reserve: Option<usize>,
+
+    /// Ed25519 key (PKCS#8 PEM) to sign each registry entry with; adds REGISTRY_SIG_* consts
+    #[arg(long, value_name = "PEM")]
+    sign_key: Option<PathBuf>,
+
+    /// Public key the loader pins for registry signatures (32-byte hex); --sign-key must match
+    #[arg(long, value_name = "HEX")]
+    signer_pubkey: Option<String>,
+
+    /// Print VK sizes (compressed vs uncompressed) and IC point counts to stderr
+    #[arg(long)]
+    size_report: bool,
+
+    /// Warn when the embedded constants exceed this many bytes (flash budget)
+    #[arg(long, value_name = "BYTES")]
+    max_bytes: Option<usize>,
+
+    /// Optional path to write the generated snippet (stdout if not set or `-`)
     #[arg(long, value_name = "PATH")]
     out: Option<PathBuf>,
 }
 
+#[derive(Debug, Subcommand)]
+enum Cmd {
+    /// Check a proof against the VK on the host before embedding the VK
+    Verify {
+        /// Verifying key file (compressed or uncompressed)
+        #[arg(long = "vk", value_name = "PATH")]
+        vk_path: PathBuf,
+
+        /// Proof file (arkworks CanonicalSerialize, e.g. zk-prove-demo's proof.bin)
+        #[arg(long = "proof", value_name = "PATH")]
+        proof_path: PathBuf,
+
+        /// Public inputs: concatenated 32-byte little-endian field elements
+        #[arg(long = "public-inputs", value_name = "PATH")]
+        inputs_path: PathBuf,
+
+        /// Curve of the key and proof
+        #[arg(long, value_enum, default_value = "bls12-381")]
+        curve: Curve,
+    },
+
+    /// Emit zk_selftest.rs: known-answer vectors for a power-on self-test of the Groth16 path
+    GenKat {
+        /// Verifying key file (compressed or uncompressed)
+        #[arg(long = "vk", value_name = "PATH")]
+        vk_path: PathBuf,
+
+        /// Sample proof (arkworks CanonicalSerialize)
+        #[arg(long = "proof", value_name = "PATH")]
+        proof_path: PathBuf,
+
+        /// Sample public inputs: concatenated 32-byte little-endian field elements
+        #[arg(long = "public-inputs", value_name = "PATH")]
+        inputs_path: PathBuf,
+
+        /// Curve of the key and proof
+        #[arg(long, value_enum, default_value = "bls12-381")]
+        curve: Curve,
+
+        /// Prefix for generated const names
+        #[arg(long, value_name = "NAME", default_value = "PROGRAM")]
+        const_prefix: String,
+
+        /// Where to write the module (stdout if not set or `-`)
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+    },
+
+    /// Re-derive PROGRAM_HASH and the VK bytes and compare them with the constants already
+    /// in a source file; exits non-zero on drift
+    Check {
+        /// Source file holding the embedded constants (Rust, or a --lang c header)
+        #[arg(long, value_name = "PATH")]
+        source: PathBuf,
+
+        /// Program/circuit ID as UTF-8 string
+        #[arg(long, value_name = "STR", group = "pid")]
+        program_id_str: Option<String>,
+
+        /// Program/circuit ID as hex (no 0x)
+        #[arg(long, value_name = "HEX", group = "pid")]
+        program_id_hex: Option<String>,
+
+        /// Program/circuit ID from raw bytes file
+        #[arg(long, value_name = "PATH", group = "pid")]
+        program_id_file: Option<PathBuf>,
+
+        /// Verifying key file the constants should have been generated from
+        #[arg(long = "vk", value_name = "PATH")]
+        vk_path: PathBuf,
+
+        /// Encoding of the --vk file
+        #[arg(long, value_enum, default_value = "arkworks")]
+        vk_format: VkFormat,
+
+        /// Curve of the verifying key
+        #[arg(long, value_enum, default_value = "bls12-381")]
+        curve: Curve,
+
+        /// Const prefix the snippet was generated with
+        #[arg(long, value_name = "NAME", default_value = "PROGRAM")]
+        const_prefix: String,
+
+        /// Domain separator the snippet was generated with
+        #[arg(long, value_name = "STR", default_value = DS_PROGRAM_DEFAULT)]
+        ds_program: String,
+    },
+}
+
 fn main() {
     if let Err(e) = run() {
         eprintln!("[x] {}", e);
@@ -57,34 +281,215 @@ fn main() {
 
 fn run() -> Result<(), String> {
     let args = Args::parse();
+    match &args.cmd {
+        Some(Cmd::Verify { vk_path, proof_path, inputs_path, curve }) => {
+            return match curve {
+                Curve::Bls12_381 => verify::<Bls12_381>(vk_path, proof_path, inputs_path, *curve),
+                Curve::Bn254 => verify::<Bn254>(vk_path, proof_path, inputs_path, *curve),
+            };
+        }
+        Some(Cmd::GenKat { vk_path, proof_path, inputs_path, curve, const_prefix, out }) => {
+            let kat = match curve {
+                Curve::Bls12_381 => gen_kat::<Bls12_381>(vk_path, proof_path, inputs_path, *curve, const_prefix)?,
+                Curve::Bn254 => gen_kat::<Bn254>(vk_path, proof_path, inputs_path, *curve, const_prefix)?,
+            };
+            return match out.as_ref().filter(|p| !is_stdio(p)) {
+                Some(path) => fs::write(path, kat).map_err(|e| format!("write {}: {}", path.display(), e)),
+                None => io::stdout().write_all(kat.as_bytes()).map_err(|e| format!("write stdout: {e}")),
+            };
+        }
+        Some(Cmd::Check {
+            source,
+            program_id_str,
+            program_id_hex,
+            program_id_file,
+            vk_path,
+            vk_format,
+            curve,
+            const_prefix,
+            ds_program,
+        }) => {
+            let pid = program_id_bytes(program_id_str.as_deref(), program_id_hex.as_deref(), program_id_file.as_deref())?;
+            let e = derive_entry(const_prefix, *curve, ds_program, pid, vk_path, *vk_format)?;
+            let expected = [
+                (format!("PROGRAM_HASH_{}", e.prefix), &e.program_hash[..]),
+                (format!("VK_{}_{}_GROTH16", e.prefix, e.curve.tag()), &e.vk[..]),
+            ];
+            return check::audit(source, &expected);
+        }
+        None => {}
+    }
+    // 1) PROGRAM_HASH and canonical VK bytes for each program
+    let (entries, ds, header) = match &args.manifest {
+        Some(path) => from_manifest(path, &args.ds_program)?,
+        None => (vec![from_args(&args)?], args.ds_program.clone(), "// --- paste into src/zk/zkverify.rs ---".to_string()),
+    };
+
+    // 2) Emit Rust snippet (or a structured document, or a blob)
+    let embeddings: Vec<emit::Embedding> = entries
+        .iter()
+        .map(|e| emit::Embedding {
+            prefix: &e.prefix,
+            curve: e.curve,
+            ds: &ds,
+            program_id: &e.program_id,
+            program_hash: &e.program_hash,
+            vk: &e.vk,
+        })
+        .collect();
+    let signer = match &args.sign_key {
+        Some(_) if args.emit == Emit::Blob || !matches!(args.format, Format::Rust) => {
+            return Err("--sign-key only applies to --format rust source output".into());
+        }
+        Some(path) => {
+            let pinned = args.signer_pubkey.as_deref().ok_or(
+                "--sign-key needs --signer-pubkey, the key the loader pins (the output never carries it)",
+            )?;
+            if u16::try_from(ds.len()).is_err() {
+                return Err("DS longer than 65535 bytes cannot be signed".into());
+            }
+            Some(load_signing_key(path, pinned)?)
+        }
+        None if args.signer_pubkey.is_some() => return Err("--signer-pubkey only applies with --sign-key".into()),
+        None => None,
+    };
+    if args.size_report || args.max_bytes.is_some() {
+        budget::report(&entries, signer.is_some(), args.size_report, args.max_bytes);
+    }
+    if args.emit == Emit::Blob {
+        if args.lang == Lang::C {
+            return Err("--emit blob writes a Rust shim; --lang c does not apply".into());
+        }
+        return write_blob(&args, &embeddings);
+    }
+    if args.blob_reserve.is_some() {
+        return Err("--blob-reserve only applies to --emit blob".into());
+    }
+    if args.lang == Lang::C && !matches!(args.format, Format::Rust) {
+        return Err("--lang c only applies to --format rust source output".into());
+    }
+    let output = match (args.format, args.manifest.is_some()) {
+        (Format::Rust, _) if args.lang == Lang::C => build_c_header(&ds, &entries, signer.as_ref()).into_bytes(),
+        (Format::Rust, _) => build_snippet(&header, &ds, &entries, signer.as_ref()).into_bytes(),
+        (Format::Json, false) => emit::json(&embeddings[0]).into_bytes(),
+        (Format::Json, true) => emit::json_list(&embeddings).into_bytes(),
+        (Format::Cbor, false) => emit::cbor(&embeddings[0]),
+        (Format::Cbor, true) => emit::cbor_list(&embeddings),
+    };
+
+    if let Some(path) = args.out.as_ref().filter(|p| !is_stdio(p)) {
+        fs::write(path, &output)
+            .map_err(|e| format!("write {}: {}", path.display(), e))?;
+    } else {
+        io::stdout().write_all(&output).map_err(|e| format!("write stdout: {e}"))?;
+    }
+
+    Ok(())
+}
 
-    // 1) Program ID bytes
-    let pid_bytes = load_program_id_bytes(&args)?;
+/* ---------------- helpers ---------------- */
 
-    // 2) PROGRAM_HASH (domain-separated)
-    let program_hash = derive_program_hash(&args.ds_program, &pid_bytes);
+fn write_blob(args: &Args, embeddings: &[emit::Embedding]) -> Result<(), String> {
+    let Some(path) = &args.out else {
+        return Err("--emit blob needs --out <PATH> for the blob (or --out - for stdout)".into());
+    };
+    if !matches!(args.format, Format::Rust) {
+        return Err("--emit blob writes its own shim; --format does not apply".into());
+    }
+    let blob = emit::blob(embeddings, args.blob_reserve)?;
+    // Raw blob for a pipeline; the shim needs a file name to include_bytes!, so none is printed
+    if is_stdio(path) {
+        return io::stdout().write_all(&blob).map_err(|e| format!("write stdout: {e}"));
+    }
+    fs::write(path, &blob).map_err(|e| format!("write {}: {}", path.display(), e))?;
 
-    // 3) VK load -> normalized to canonical compressed bytes
-    let vk_bytes = load_and_normalize_vk(&args.vk_path)?;
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    print!("{}", emit::blob_shim(&name, &blob));
+    Ok(())
+}
 
-    // 4) Emit Rust snippet
-    let prefix = sanitize_ident(&args.const_prefix);
-    let snippet = build_snippet(&prefix, &args.ds_program, &program_hash, &vk_bytes);
+/// `-` stands for stdin (`--vk`) or stdout (`--out`)
+fn is_stdio(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
 
-    if let Some(path) = &args.out {
-        fs::write(path, snippet.as_bytes())
-            .map_err(|e| format!("write {}: {}", path.display(), e))?;
-    } else {
-        print!("{snippet}");
+fn read_input(path: &Path, what: &str) -> Result<Vec<u8>, String> {
+    if is_stdio(path) {
+        let mut buf = Vec::new();
+        io::stdin().read_to_end(&mut buf).map_err(|e| format!("read {what} from stdin: {e}"))?;
+        return Ok(buf);
     }
+    fs::read(path).map_err(|e| format!("read {what} {}: {e}", path.display()))
+}
 
-    Ok(())
+/// One program's derived constants
+struct Entry {
+    prefix: String,
+    curve: Curve,
+    program_id: Vec<u8>,
+    program_hash: [u8; 32],
+    vk: Vec<u8>,
+    stats: VkStats,
 }
 
-/* ---------------- helpers ---------------- */
+/// Size facts about a VK, for `--size-report` / `--max-bytes`
+struct VkStats {
+    uncompressed_len: usize,
+    /// gamma_abc_g1 length: one point per public input plus the constant term
+    ic_points: usize,
+    g1_compressed_len: usize,
+}
 
-fn load_program_id_bytes(args: &Args) -> Result<Vec<u8>, String> {
-    match (&args.program_id_str, &args.program_id_hex, &args.program_id_file) {
+fn derive_entry(prefix: &str, curve: Curve, ds: &str, program_id: Vec<u8>, vk_path: &Path, vk_format: VkFormat) -> Result<Entry, String> {
+    // PROGRAM_HASH (domain-separated)
+    let program_hash = derive_program_hash(ds, &program_id);
+
+    // VK load -> normalized to canonical compressed bytes
+    let (vk, stats) = match curve {
+        Curve::Bls12_381 => load_and_normalize_vk::<Bls12_381>(vk_path, curve, vk_format)?,
+        Curve::Bn254 => load_and_normalize_vk::<Bn254>(vk_path, curve, vk_format)?,
+    };
+    Ok(Entry { prefix: sanitize_ident(prefix), curve, program_id, program_hash, vk, stats })
+}
+
+fn from_args(args: &Args) -> Result<Entry, String> {
+    let vk_path = args.vk_path.as_ref().ok_or("--vk is required")?;
+    let pid_bytes = program_id_bytes(args.program_id_str.as_deref(), args.program_id_hex.as_deref(), args.program_id_file.as_deref())?;
+    derive_entry(&args.const_prefix, args.curve, &args.ds_program, pid_bytes, vk_path, args.vk_format)
+}
+
+/// Every manifest entry, checked so the generated registry compiles and is unambiguous
+fn from_manifest(path: &Path, ds_default: &str) -> Result<(Vec<Entry>, String, String), String> {
+    let m = manifest::load(path)?;
+    let ds = m.ds_program.unwrap_or_else(|| ds_default.to_string());
+    // VK validation (subgroup checks) dominates; derive in parallel, then check in manifest
+    // order so output and the reported error never depend on scheduling
+    let derived: Vec<(String, Result<Entry, String>)> = m
+        .programs
+        .into_par_iter()
+        .map(|p| {
+            let e = derive_entry(&p.prefix, p.curve, &ds, p.program_id, &p.vk_path, p.vk_format);
+            (p.prefix, e)
+        })
+        .collect();
+    let mut entries: Vec<Entry> = Vec::with_capacity(derived.len());
+    for (prefix, e) in derived {
+        let e = e.map_err(|err| format!("{prefix}: {err}"))?;
+        if let Some(dup) = entries.iter().find(|o| o.prefix == e.prefix) {
+            return Err(format!("{prefix}: prefix collides with another program as {}", dup.prefix));
+        }
+        if let Some(dup) = entries.iter().find(|o| o.program_hash == e.program_hash) {
+            return Err(format!("{prefix}: same program ID (and so PROGRAM_HASH) as {}", dup.prefix));
+        }
+        entries.push(e);
+    }
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let header = format!("// --- zkverify registry: @generated by zk-embed from {name}; do not edit ---");
+    Ok((entries, ds, header))
+}
+
+fn program_id_bytes(s: Option<&str>, h: Option<&str>, file: Option<&Path>) -> Result<Vec<u8>, String> {
+    match (s, h, file) {
         (Some(s), None, None) => Ok(s.as_bytes().to_vec()),
         (None, Some(h), None) => {
             let h = h.trim().trim_start_matches("0x").trim_start_matches("0X");
@@ -95,39 +500,188 @@ fn load_program_id_bytes(args: &Args) -> Result<Vec<u8>, String> {
     }
 }
 
+/// The signing key, refused unless its public half is the one the loader pins
+fn load_signing_key(path: &Path, pinned_hex: &str) -> Result<SigningKey, String> {
+    let pem = fs::read_to_string(path).map_err(|e| format!("read signing key {}: {e}", path.display()))?;
+    let key = SigningKey::from_pkcs8_pem(&pem).map_err(|e| format!("{}: not an Ed25519 PKCS#8 PEM key: {e}", path.display()))?;
+    let pinned = hex::decode(pinned_hex.trim().trim_start_matches("0x")).map_err(|e| format!("signer-pubkey: {e}"))?;
+    if pinned.len() != 32 {
+        return Err(format!("signer-pubkey: {} bytes, expected 32", pinned.len()));
+    }
+    let actual = key.verifying_key().to_bytes();
+    if pinned != actual {
+        return Err(format!(
+            "{}: public key {} is not --signer-pubkey {}",
+            path.display(),
+            hex::encode(actual),
+            hex::encode(&pinned)
+        ));
+    }
+    Ok(key)
+}
+
+/// What REGISTRY_SIG_<PREFIX> signs: len(DS) as u16 LE ‖ DS ‖ PROGRAM_HASH ‖ VK bytes.
+/// The length prefix keeps a DS from absorbing the start of the hash. Callers check that
+/// the DS fits in a u16.
+fn registry_sig_message(ds: &str, e: &Entry) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(2 + ds.len() + 32 + e.vk.len());
+    msg.extend_from_slice(&(ds.len() as u16).to_le_bytes());
+    msg.extend_from_slice(ds.as_bytes());
+    msg.extend_from_slice(&e.program_hash);
+    msg.extend_from_slice(&e.vk);
+    msg
+}
+
 fn derive_program_hash(ds_program: &str, program_id_bytes: &[u8]) -> [u8; 32] {
     let mut hasher = blake3::Hasher::new_derive_key(ds_program);
     hasher.update(program_id_bytes);
     *hasher.finalize().as_bytes()
 }
 
-fn load_and_normalize_vk(path: &PathBuf) -> Result<Vec<u8>, String> {
-    let vk_raw = fs::read(path).map_err(|e| format!("read verifying key {}: {e}", path.display()))?;
+fn load_and_normalize_vk<E: Pairing>(path: &Path, curve: Curve, format: VkFormat) -> Result<(Vec<u8>, VkStats), String> {
+    let vk = load_vk::<E>(path, curve, format)?;
+    let mut out = Vec::new();
+    vk.serialize_with_mode(&mut out, Compress::Yes)
+        .map_err(|_| "failed to serialize VK in compressed canonical form".to_string())?;
+    let stats = VkStats {
+        uncompressed_len: vk.serialized_size(Compress::No),
+        ic_points: vk.gamma_abc_g1.len(),
+        g1_compressed_len: vk.alpha_g1.serialized_size(Compress::Yes),
+    };
+    Ok((out, stats))
+}
+
+fn load_vk<E: Pairing>(path: &Path, curve: Curve, format: VkFormat) -> Result<VerifyingKey<E>, String> {
+    let mut vk_raw = read_input(path, "verifying key")?;
     if vk_raw.is_empty() {
         return Err("verifying key file is empty".into());
     }
+    if format == VkFormat::SnarkjsJson {
+        vk_raw = snarkjs::to_arkworks(&vk_raw, curve)?;
+    }
 
     // Try compressed first, then uncompressed
-    let vk = VerifyingKey::<Bls12_381>::deserialize_with_mode(
+    let vk = VerifyingKey::<E>::deserialize_with_mode(
         &mut Cursor::new(&vk_raw),
         Compress::Yes,
         Validate::Yes,
     )
     .or_else(|_| {
-        VerifyingKey::<Bls12_381>::deserialize_with_mode(
+        VerifyingKey::<E>::deserialize_with_mode(
             &mut Cursor::new(&vk_raw),
             Compress::No,
             Validate::Yes,
         )
     })
-    .map_err(|_| "failed to deserialize verifying key (neither compressed nor uncompressed)".to_string())?;
+    .map_err(|_| format!("failed to deserialize {} verifying key (neither compressed nor uncompressed)", curve.name()))?;
+    Ok(vk)
+}
+
+/// Host-side Groth16 check: catches a VK that does not belong to the prover's circuit.
+fn verify<E: Pairing>(vk_path: &Path, proof_path: &Path, inputs_path: &Path, curve: Curve) -> Result<(), String> {
+    let (vk, proof, inputs) = load_vectors::<E>(vk_path, proof_path, inputs_path, curve)?;
 
+    let pvk = ark_groth16::prepare_verifying_key(&vk);
+    let ok = Groth16::<E>::verify_proof(&pvk, &proof, &inputs).map_err(|e| format!("verify: {e}"))?;
+    if !ok {
+        return Err("proof does NOT verify against this VK; do not embed it (wrong circuit, key or inputs?)".into());
+    }
+    println!("[+] proof verifies against {} ({} public inputs, {})", vk_path.display(), inputs.len(), curve.name());
+    Ok(())
+}
+
+/// Known-answer vectors from one sample: the proof as given, with whatever result the host
+/// verifier reports, plus the same proof with its first public input bumped, which must fail.
+fn gen_kat<E: Pairing>(vk_path: &Path, proof_path: &Path, inputs_path: &Path, curve: Curve, prefix: &str) -> Result<String, String> {
+    let (vk, proof, inputs) = load_vectors::<E>(vk_path, proof_path, inputs_path, curve)?;
+
+    // The positive vector is the half a stub verifier cannot fake; never emit it as a rejection
+    let pvk = ark_groth16::prepare_verifying_key(&vk);
+    let verify = |p: &Proof<E>, xs: &[E::ScalarField]| Groth16::<E>::verify_proof(&pvk, p, xs).map_err(|e| format!("verify: {e}"));
+    if !verify(&proof, &inputs)? {
+        return Err("the sample proof does not verify against the VK and public inputs; no known-good vector".into());
+    }
+
+    // Inputs keep the file layout: one 32-byte scalar after another, no length prefix
+    let scalars = |xs: &[E::ScalarField]| -> Result<Vec<u8>, String> {
+        Ok(xs.iter().map(to_compressed).collect::<Result<Vec<_>, _>>()?.concat())
+    };
+
+    // Negative vector, derived here rather than taken from the caller: the first public input
+    // incremented, or with no inputs the proof's A point negated
+    let bad = match inputs.first() {
+        Some(first) => {
+            let mut bad = inputs.clone();
+            bad[0] = *first + E::ScalarField::one();
+            if verify(&proof, &bad)? {
+                return Err("proof still verifies with a modified public input; no negative vector possible".into());
+            }
+            selftest::Negative::Inputs(scalars(&bad)?)
+        }
+        None => {
+            let mut bad = proof.clone();
+            let a: E::G1 = bad.a.into();
+            bad.a = (-a).into();
+            if verify(&bad, &inputs)? {
+                return Err("a proof with A negated still verifies; no negative vector possible".into());
+            }
+            selftest::Negative::Proof(to_compressed(&bad)?)
+        }
+    };
+
+    let kat = selftest::Kat {
+        prefix: sanitize_ident(prefix),
+        curve,
+        vk: to_compressed(&vk)?,
+        proof: to_compressed(&proof)?,
+        inputs: scalars(&inputs)?,
+        bad,
+    };
+    Ok(selftest::module(&kat))
+}
+
+fn to_compressed<T: CanonicalSerialize>(v: &T) -> Result<Vec<u8>, String> {
     let mut out = Vec::new();
-    vk.serialize_with_mode(&mut out, Compress::Yes)
-        .map_err(|_| "failed to serialize VK in compressed canonical form".to_string())?;
+    v.serialize_compressed(&mut out).map_err(|e| format!("serialize: {e}"))?;
     Ok(out)
 }
 
+/// VK, proof and public inputs for `verify` and `gen-kat`, with the input count checked
+#[allow(clippy::type_complexity)]
+fn load_vectors<E: Pairing>(
+    vk_path: &Path,
+    proof_path: &Path,
+    inputs_path: &Path,
+    curve: Curve,
+) -> Result<(VerifyingKey<E>, Proof<E>, Vec<E::ScalarField>), String> {
+    let vk = load_vk::<E>(vk_path, curve, VkFormat::Arkworks)?;
+
+    let proof_raw = fs::read(proof_path).map_err(|e| format!("read proof {}: {e}", proof_path.display()))?;
+    let proof = Proof::<E>::deserialize_with_mode(&mut Cursor::new(&proof_raw), Compress::Yes, Validate::Yes)
+        .or_else(|_| Proof::<E>::deserialize_with_mode(&mut Cursor::new(&proof_raw), Compress::No, Validate::Yes))
+        .map_err(|_| format!("failed to deserialize {} proof (neither compressed nor uncompressed)", curve.name()))?;
+
+    let inputs_raw = fs::read(inputs_path).map_err(|e| format!("read public inputs {}: {e}", inputs_path.display()))?;
+    if inputs_raw.len() % 32 != 0 {
+        return Err(format!("public inputs are {} bytes, not a multiple of 32", inputs_raw.len()));
+    }
+    let inputs = inputs_raw
+        .chunks_exact(32)
+        .enumerate()
+        .map(|(i, c)| {
+            E::ScalarField::deserialize_with_mode(c, Compress::Yes, Validate::Yes)
+                .map_err(|_| format!("public input {i} is not a canonical {} scalar", curve.name()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // gamma_abc_g1 holds one point for the constant term plus one per public input
+    let expected = vk.gamma_abc_g1.len().saturating_sub(1);
+    if inputs.len() != expected {
+        return Err(format!("VK expects {expected} public inputs, file has {}", inputs.len()));
+    }
+    Ok((vk, proof, inputs))
+}
+
 fn sanitize_ident(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
     for ch in s.chars() {
@@ -144,60 +698,129 @@ fn sanitize_ident(s: &str) -> String {
     }
 }
 
-fn build_snippet(prefix: &str, ds: &str, program_hash: &[u8; 32], vk_bytes: &[u8]) -> String {
+fn build_snippet(header: &str, ds: &str, entries: &[Entry], signer: Option<&SigningKey>) -> String {
     let mut out = String::new();
 
     // Header
-    out.push_str("// --- paste into src/zk/zkverify.rs ---\n");
+    out.push_str(header);
+    out.push('\n');
     out.push_str(&format!("// DS: {ds}\n\n"));
 
-    // PROGRAM_HASH const
-    out.push_str(&format!("pub const PROGRAM_HASH_{prefix}: [u8; 32] = [\n"));
-    for (i, b) in program_hash.iter().enumerate() {
-        if i % 16 == 0 {
-            out.push_str("    ");
-        }
-        out.push_str(&format!("0x{b:02x}"));
-        if i != 31 {
-            out.push_str(", ");
-        }
-        if i % 16 == 15 {
-            out.push('\n');
-        }
+    // Only the signatures; a key shipped next to them would vouch for whatever replaced them
+    if let Some(key) = signer {
+        out.push_str("// REGISTRY_SIG_<PREFIX> = ed25519(u16le(len(DS)) || DS || PROGRAM_HASH || VK bytes), verified\n");
+        out.push_str(&format!("// against the signer key pinned in the loader: {}\n\n", hex::encode(key.verifying_key().as_bytes())));
     }
-    out.push_str("];\n\n");
 
-    // VK bytes const
-    out.push_str(&format!(
-        "pub const VK_{prefix}_BLS12_381_GROTH16: &[u8] = &[\n"
-    ));
-    for (i, b) in vk_bytes.iter().enumerate() {
-        if i % 16 == 0 {
-            out.push_str("    ");
-        }
-        out.push_str(&format!("0x{b:02x}"));
-        if i + 1 != vk_bytes.len() {
-            out.push_str(", ");
-        }
-        if i % 16 == 15 {
-            out.push('\n');
+    for e in entries {
+        push_consts(&mut out, e);
+        if let Some(key) = signer {
+            let sig = key.sign(&registry_sig_message(ds, e));
+            push_array(&mut out, &format!("REGISTRY_SIG_{}", e.prefix), &sig.to_bytes());
         }
     }
-    out.push_str("];\n\n");
 
-    // Mapping snippet
+    // Mapping snippet; the VK bytes alone don't say which pairing to verify them on
     out.push_str("#[cfg(feature = \"zk-groth16\")]\n");
-    out.push_str("fn program_vk_lookup(program_hash: &[u8; 32]) -> Option<&'static [u8]> {\n");
-    out.push_str(&format!(
-        "    if ct_eq32(program_hash, &PROGRAM_HASH_{prefix}) {{\n"
-    ));
-    out.push_str(&format!(
-        "        return Some(VK_{prefix}_BLS12_381_GROTH16);\n"
-    ));
-    out.push_str("    }\n");
+    out.push_str("#[allow(dead_code)]\n");
+    out.push_str("#[derive(Clone, Copy, Debug, PartialEq, Eq)]\n");
+    out.push_str("pub enum Curve {\n    Bls12_381,\n    Bn254,\n}\n\n");
+    out.push_str("#[cfg(feature = \"zk-groth16\")]\n");
+    out.push_str("fn program_vk_lookup(program_hash: &[u8; 32]) -> Option<(Curve, &'static [u8])> {\n");
+    for e in entries {
+        out.push_str(&format!(
+            "    if ct_eq32(program_hash, &PROGRAM_HASH_{}) {{\n",
+            e.prefix
+        ));
+        out.push_str(&format!(
+            "        return Some((Curve::{}, VK_{}_{}_GROTH16));\n",
+            e.curve.variant(),
+            e.prefix,
+            e.curve.tag()
+        ));
+        out.push_str("    }\n");
+    }
     out.push_str("    None\n");
     out.push_str("}\n");
 
     out.push_str("\n// done.\n");
     out
 }
+
+/// Same derivation and names as `build_snippet`, as a self-contained C header
+fn build_c_header(ds: &str, entries: &[Entry], signer: Option<&SigningKey>) -> String {
+    let mut out = String::new();
+    out.push_str("/* zkverify registry: @generated by zk-embed; do not edit */\n");
+    out.push_str(&format!("/* DS: {ds} */\n\n"));
+    out.push_str("#ifndef NONOS_ZKVERIFY_REGISTRY_H\n#define NONOS_ZKVERIFY_REGISTRY_H\n\n");
+    out.push_str("#include <stddef.h>\n#include <stdint.h>\n\n");
+
+    if let Some(key) = signer {
+        out.push_str("/* REGISTRY_SIG_<PREFIX> = ed25519(u16le(len(DS)) || DS || PROGRAM_HASH || VK bytes), verified\n");
+        out.push_str(&format!(" * against the signer key pinned in the loader: {} */\n\n", hex::encode(key.verifying_key().as_bytes())));
+    }
+
+    for e in entries {
+        let vk_name = format!("VK_{}_{}_GROTH16", e.prefix, e.curve.tag());
+        push_c_array(&mut out, &format!("PROGRAM_HASH_{}", e.prefix), &e.program_hash);
+        push_c_array(&mut out, &vk_name, &e.vk);
+        out.push_str(&format!("static const size_t {vk_name}_LEN = {};\n\n", e.vk.len()));
+        if let Some(key) = signer {
+            let sig = key.sign(&registry_sig_message(ds, e));
+            push_c_array(&mut out, &format!("REGISTRY_SIG_{}", e.prefix), &sig.to_bytes());
+        }
+    }
+
+    // Mapping snippet; the compare does not exit early, like ct_eq32 on the Rust side
+    out.push_str("static inline int zk_ct_eq32(const uint8_t a[32], const uint8_t b[32]) {\n");
+    out.push_str("    uint8_t d = 0;\n");
+    out.push_str("    for (size_t i = 0; i < 32; i++) {\n");
+    out.push_str("        d |= (uint8_t)(a[i] ^ b[i]);\n");
+    out.push_str("    }\n");
+    out.push_str("    return d == 0;\n");
+    out.push_str("}\n\n");
+    out.push_str("typedef enum { ZK_CURVE_BLS12_381, ZK_CURVE_BN254 } zk_curve;\n\n");
+    out.push_str("static inline const uint8_t *program_vk_lookup(const uint8_t program_hash[32], size_t *vk_len, zk_curve *curve) {\n");
+    for e in entries {
+        let vk_name = format!("VK_{}_{}_GROTH16", e.prefix, e.curve.tag());
+        out.push_str(&format!("    if (zk_ct_eq32(program_hash, PROGRAM_HASH_{})) {{\n", e.prefix));
+        out.push_str(&format!("        *vk_len = {vk_name}_LEN;\n"));
+        out.push_str(&format!("        *curve = ZK_CURVE_{};\n", e.curve.tag()));
+        out.push_str(&format!("        return {vk_name};\n"));
+        out.push_str("    }\n");
+    }
+    out.push_str("    *vk_len = 0;\n");
+    out.push_str("    return NULL;\n");
+    out.push_str("}\n\n");
+
+    out.push_str("#endif /* NONOS_ZKVERIFY_REGISTRY_H */\n");
+    out
+}
+
+fn push_consts(out: &mut String, e: &Entry) {
+    push_array(out, &format!("PROGRAM_HASH_{}", e.prefix), &e.program_hash);
+    push_slice(out, &format!("VK_{}_{}_GROTH16", e.prefix, e.curve.tag()), &e.vk);
+}
+
+/// Fixed-size `pub const NAME: [u8; N]`, 16 bytes per line
+fn push_array(out: &mut String, name: &str, bytes: &[u8]) {
+    out.push_str(&format!("pub const {name}: [u8; {}] = [\n{}];\n\n", bytes.len(), hex_rows(bytes)));
+}
+
+/// `pub const NAME: &[u8]`, for byte strings whose length is not part of the interface
+fn push_slice(out: &mut String, name: &str, bytes: &[u8]) {
+    out.push_str(&format!("pub const {name}: &[u8] = &[\n{}];\n\n", hex_rows(bytes)));
+}
+
+/// `static const uint8_t NAME[N]`, 16 bytes per line
+fn push_c_array(out: &mut String, name: &str, bytes: &[u8]) {
+    out.push_str(&format!("static const uint8_t {name}[{}] = {{\n{}}};\n\n", bytes.len(), hex_rows(bytes)));
+}
+
+fn hex_rows(bytes: &[u8]) -> String {
+    let rows: Vec<String> = bytes
+        .chunks(16)
+        .map(|line| line.iter().map(|b| format!("0x{b:02x}")).collect::<Vec<_>>().join(", "))
+        .collect();
+    format!("    {}\n", rows.join(",\n    "))
+}
@@ -0,0 +1,108 @@
+//! `--manifest registry.toml`: every program in one run, one registry, one lookup function.
+//!
+//! ```toml
+//! ds_program = "NONOS:ZK:PROGRAM:v1"    # optional, defaults to --ds-program
+//!
+//! [[program]]
+//! prefix = "ATTEST_V1"
+//! program_id_str = "zkmod-attestation-program-v1"   # or program_id_hex / program_id_file
+//! vk = "vks/attest_v1.bin"                          # relative to the manifest
+//! curve = "bls12-381"                               # optional
+//! vk_format = "arkworks"                            # optional; or "snarkjs-json"
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use toml::{Table, Value};
+
+use crate::{Curve, VkFormat};
+
+pub struct Program {
+    pub prefix: String,
+    pub program_id: Vec<u8>,
+    pub vk_path: PathBuf,
+    pub curve: Curve,
+    pub vk_format: VkFormat,
+}
+
+pub struct Manifest {
+    pub ds_program: Option<String>,
+    pub programs: Vec<Program>,
+}
+
+pub fn load(path: &Path) -> Result<Manifest, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("read manifest {}: {e}", path.display()))?;
+    let doc: Table = text.parse().map_err(|e| format!("{}: {e}", path.display()))?;
+    let base = path.parent().unwrap_or(Path::new("."));
+
+    for key in doc.keys() {
+        if key != "ds_program" && key != "program" {
+            return Err(format!("{}: unknown key `{key}`", path.display()));
+        }
+    }
+    let ds_program = match doc.get("ds_program") {
+        None => None,
+        Some(Value::String(s)) => Some(s.clone()),
+        Some(_) => return Err("ds_program: expected a string".into()),
+    };
+    let entries = match doc.get("program") {
+        Some(Value::Array(a)) if !a.is_empty() => a,
+        _ => return Err(format!("{}: no [[program]] entries", path.display())),
+    };
+
+    let mut programs = Vec::with_capacity(entries.len());
+    for (i, entry) in entries.iter().enumerate() {
+        let Value::Table(t) = entry else {
+            return Err(format!("program[{i}]: expected a table"));
+        };
+        programs.push(program(t, i, base)?);
+    }
+    Ok(Manifest { ds_program, programs })
+}
+
+/* ---------------- helpers ---------------- */
+
+const FIELDS: &[&str] = &["prefix", "program_id_str", "program_id_hex", "program_id_file", "vk", "curve", "vk_format"];
+
+fn program(t: &Table, i: usize, base: &Path) -> Result<Program, String> {
+    if let Some(k) = t.keys().find(|k| !FIELDS.contains(&k.as_str())) {
+        return Err(format!("program[{i}].{k}: unknown field"));
+    }
+    let string = |key: &str| -> Result<Option<&str>, String> {
+        match t.get(key) {
+            None => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s)),
+            Some(_) => Err(format!("program[{i}].{key}: expected a string")),
+        }
+    };
+
+    let prefix = string("prefix")?.ok_or_else(|| format!("program[{i}]: `prefix` is required"))?;
+    let vk = string("vk")?.ok_or_else(|| format!("program[{i}]: `vk` is required"))?;
+    let curve = match string("curve")? {
+        None | Some("bls12-381") => Curve::Bls12_381,
+        Some("bn254") => Curve::Bn254,
+        Some(other) => return Err(format!("program[{i}].curve: expected bls12-381|bn254, got `{other}`")),
+    };
+    let vk_format = match string("vk_format")? {
+        None | Some("arkworks") => VkFormat::Arkworks,
+        Some("snarkjs-json") => VkFormat::SnarkjsJson,
+        Some(other) => return Err(format!("program[{i}].vk_format: expected arkworks|snarkjs-json, got `{other}`")),
+    };
+    let program_id = match (string("program_id_str")?, string("program_id_hex")?, string("program_id_file")?) {
+        (Some(s), None, None) => s.as_bytes().to_vec(),
+        (None, Some(h), None) => {
+            let h = h.trim().trim_start_matches("0x").trim_start_matches("0X");
+            hex::decode(h).map_err(|e| format!("program[{i}].program_id_hex: {e}"))?
+        }
+        (None, None, Some(p)) => {
+            let p = base.join(p);
+            fs::read(&p).map_err(|e| format!("program[{i}]: read program_id_file {}: {e}", p.display()))?
+        }
+        _ => return Err(format!("program[{i}]: provide exactly one of program_id_str | program_id_hex | program_id_file")),
+    };
+
+    Ok(Program { prefix: prefix.to_string(), program_id, vk_path: base.join(vk), curve, vk_format })
+}
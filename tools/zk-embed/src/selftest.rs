@@ -0,0 +1,85 @@
+//! `zk-embed gen-kat`: a `zk_selftest.rs` module of known-answer vectors, so the bootloader
+//! can exercise its Groth16 verifier at power-on before trusting it with a real capsule.
+//!
+//! The module is standalone: it carries its own copy of the VK instead of naming a registry
+//! constant, so it still works when the registry is linked from a blob.
+
+use crate::{emit::curve_id, push_slice, Curve};
+
+/// One sample, already serialized (compressed points, 32-byte LE scalars). The sample is
+/// known to verify on the host; `bad` is known not to.
+pub struct Kat {
+    pub prefix: String,
+    pub curve: Curve,
+    pub vk: Vec<u8>,
+    pub proof: Vec<u8>,
+    pub inputs: Vec<u8>,
+    pub bad: Negative,
+}
+
+/// The derived rejection vector: the one part that differs from the sample
+pub enum Negative {
+    /// The inputs with the first one incremented
+    Inputs(Vec<u8>),
+    /// The proof with A negated, for circuits without public inputs
+    Proof(Vec<u8>),
+}
+
+pub fn module(k: &Kat) -> String {
+    let p = &k.prefix;
+    let mut out = String::new();
+    out.push_str("// --- zk_selftest.rs: @generated by zk-embed gen-kat; do not edit ---\n");
+    out.push_str(&format!("// Groth16 known-answer vectors ({}, compressed). The caller runs every entry of\n", k.curve.name()));
+    out.push_str("// ZK_SELFTEST_KATS through the verifier and, on any mismatch, refuses ZK-gated boot.\n\n");
+
+    out.push_str("// `ZkKat::curve` values: the same curve ids as the registry blob\n");
+    out.push_str(&format!("pub const ZK_CURVE_BLS12_381: u8 = {};\n", curve_id(Curve::Bls12_381)));
+    out.push_str(&format!("pub const ZK_CURVE_BN254: u8 = {};\n\n", curve_id(Curve::Bn254)));
+
+    out.push_str("pub struct ZkKat {\n");
+    out.push_str("    pub name: &'static str,\n");
+    out.push_str("    pub curve: u8,\n");
+    out.push_str("    pub vk: &'static [u8],\n");
+    out.push_str("    pub proof: &'static [u8],\n");
+    out.push_str("    /// Concatenated 32-byte little-endian scalars\n");
+    out.push_str("    pub public_inputs: &'static [u8],\n");
+    out.push_str("    pub expect: bool,\n");
+    out.push_str("}\n\n");
+
+    push_slice(&mut out, &format!("KAT_{p}_VK"), &k.vk);
+    push_slice(&mut out, &format!("KAT_{p}_PROOF"), &k.proof);
+    push_slice(&mut out, &format!("KAT_{p}_INPUTS"), &k.inputs);
+    let (bad_name, bad_proof, bad_inputs) = match &k.bad {
+        Negative::Inputs(bad) => {
+            push_slice(&mut out, &format!("KAT_{p}_INPUTS_BAD"), bad);
+            ("modified-input", format!("KAT_{p}_PROOF"), format!("KAT_{p}_INPUTS_BAD"))
+        }
+        Negative::Proof(bad) => {
+            push_slice(&mut out, &format!("KAT_{p}_PROOF_BAD"), bad);
+            ("modified-proof", format!("KAT_{p}_PROOF_BAD"), format!("KAT_{p}_INPUTS"))
+        }
+    };
+
+    let curve = match k.curve {
+        Curve::Bls12_381 => "ZK_CURVE_BLS12_381",
+        Curve::Bn254 => "ZK_CURVE_BN254",
+    };
+    out.push_str("pub const ZK_SELFTEST_KATS: &[ZkKat] = &[\n");
+    push_entry(&mut out, &format!("{p}/sample"), curve, p, &format!("KAT_{p}_PROOF"), &format!("KAT_{p}_INPUTS"), true);
+    push_entry(&mut out, &format!("{p}/{bad_name}"), curve, p, &bad_proof, &bad_inputs, false);
+    out.push_str("];\n");
+    out
+}
+
+/* ---------------- helpers ---------------- */
+
+fn push_entry(out: &mut String, name: &str, curve: &str, p: &str, proof: &str, inputs: &str, expect: bool) {
+    out.push_str("    ZkKat {\n");
+    out.push_str(&format!("        name: \"{name}\",\n"));
+    out.push_str(&format!("        curve: {curve},\n"));
+    out.push_str(&format!("        vk: KAT_{p}_VK,\n"));
+    out.push_str(&format!("        proof: {proof},\n"));
+    out.push_str(&format!("        public_inputs: {inputs},\n"));
+    out.push_str(&format!("        expect: {expect},\n"));
+    out.push_str("    },\n");
+}
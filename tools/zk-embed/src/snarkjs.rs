@@ -0,0 +1,111 @@
+//! snarkjs `verification_key.json` → arkworks bytes (`--vk-format snarkjs-json`).
+//!
+//! snarkjs writes points as projective decimal strings: G1 `[x, y, z]`, G2
+//! `[[x.c0, x.c1], [y.c0, y.c1], [z.c0, z.c1]]`, always normalized to z = 1 (or z = 0 for
+//! the point at infinity). The result is serialized uncompressed and then goes through the
+//! same validating deserialization as an arkworks key, so on-curve and subgroup checks are
+//! not duplicated here.
+
+use serde_json::Value;
+
+use crate::Curve;
+
+pub fn to_arkworks(json: &[u8], curve: Curve) -> Result<Vec<u8>, String> {
+    let vk: Value = serde_json::from_slice(json).map_err(|e| format!("snarkjs VK: {e}"))?;
+
+    let protocol = vk.get("protocol").and_then(Value::as_str).unwrap_or("");
+    if protocol != "groth16" {
+        return Err(format!("snarkjs VK: protocol `{protocol}`, only groth16 is supported"));
+    }
+    let named = vk.get("curve").and_then(Value::as_str).unwrap_or("");
+    let matches = match curve {
+        Curve::Bn254 => named == "bn128" || named == "bn254",
+        Curve::Bls12_381 => named == "bls12381" || named == "bls12-381",
+    };
+    if !matches {
+        return Err(format!("snarkjs VK is for curve `{named}`, but --curve is {}", curve.name()));
+    }
+
+    let out = match curve {
+        Curve::Bls12_381 => bls12_381(&vk)?,
+        Curve::Bn254 => bn254(&vk)?,
+    };
+
+    if let Some(n) = vk.get("nPublic").and_then(Value::as_u64) {
+        let ic = vk.get("IC").and_then(Value::as_array).map_or(0, Vec::len);
+        if ic != n as usize + 1 {
+            return Err(format!("snarkjs VK: nPublic is {n} but IC has {ic} points (expected {})", n + 1));
+        }
+    }
+    Ok(out)
+}
+
+/* ---------------- helpers ---------------- */
+
+fn strings<'a>(key: &str, v: &'a Value, n: usize) -> Result<Vec<&'a Value>, String> {
+    match v.as_array() {
+        Some(a) if a.len() == n => Ok(a.iter().collect()),
+        _ => Err(format!("snarkjs VK: `{key}` is not an array of {n}")),
+    }
+}
+
+fn decimal<'a>(key: &str, v: &'a Value) -> Result<&'a str, String> {
+    v.as_str().ok_or_else(|| format!("snarkjs VK: `{key}` coordinate is not a decimal string"))
+}
+
+/// One converter per curve; the arkworks type names are the same across curve crates.
+macro_rules! converter {
+    ($name:ident, $krate:ident, $engine:ident) => {
+        fn $name(vk: &Value) -> Result<Vec<u8>, String> {
+            use std::str::FromStr;
+
+            use ark_serialize::{CanonicalSerialize, Compress};
+            use $krate::{$engine, Fq, Fq2, G1Affine, G2Affine};
+
+            let field = |key: &str, v: &Value| -> Result<Fq, String> {
+                let s = decimal(key, v)?;
+                Fq::from_str(s).map_err(|_| format!("snarkjs VK: `{key}` coordinate {s} is not a field element"))
+            };
+            let get = |key: &str| vk.get(key).ok_or_else(|| format!("snarkjs VK: missing `{key}`"));
+
+            let g1 = |key: &str, v: &Value| -> Result<G1Affine, String> {
+                let c = strings(key, v, 3)?;
+                match decimal(key, c[2])? {
+                    "0" => Ok(G1Affine::identity()),
+                    "1" => Ok(G1Affine::new_unchecked(field(key, c[0])?, field(key, c[1])?)),
+                    _ => Err(format!("snarkjs VK: `{key}` is not normalized (z must be 1)")),
+                }
+            };
+            let g2 = |key: &str, v: &Value| -> Result<G2Affine, String> {
+                let c = strings(key, v, 3)?;
+                let fq2 = |v: &Value| -> Result<Fq2, String> {
+                    let p = strings(key, v, 2)?;
+                    Ok(Fq2::new(field(key, p[0])?, field(key, p[1])?))
+                };
+                let z = strings(key, c[2], 2)?;
+                match (decimal(key, z[0])?, decimal(key, z[1])?) {
+                    ("0", "0") => Ok(G2Affine::identity()),
+                    ("1", "0") => Ok(G2Affine::new_unchecked(fq2(c[0])?, fq2(c[1])?)),
+                    _ => Err(format!("snarkjs VK: `{key}` is not normalized (z must be 1)")),
+                }
+            };
+
+            let ic = get("IC")?.as_array().ok_or("snarkjs VK: `IC` is not an array")?;
+            let vk = ark_groth16::VerifyingKey::<$engine> {
+                alpha_g1: g1("vk_alpha_1", get("vk_alpha_1")?)?,
+                beta_g2: g2("vk_beta_2", get("vk_beta_2")?)?,
+                gamma_g2: g2("vk_gamma_2", get("vk_gamma_2")?)?,
+                delta_g2: g2("vk_delta_2", get("vk_delta_2")?)?,
+                gamma_abc_g1: ic.iter().map(|p| g1("IC", p)).collect::<Result<_, _>>()?,
+            };
+
+            let mut out = Vec::new();
+            vk.serialize_with_mode(&mut out, Compress::No)
+                .map_err(|e| format!("snarkjs VK: serialize: {e}"))?;
+            Ok(out)
+        }
+    };
+}
+
+converter!(bls12_381, ark_bls12_381, Bls12_381);
+converter!(bn254, ark_bn254, Bn254);
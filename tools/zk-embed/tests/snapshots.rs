@@ -0,0 +1,555 @@
+//! Snapshot tests for zk-embed output: every case runs the real binary and diffs its stdout
+//! (or stderr, for failures) against `tests/snapshots/<case>.snap`.
+//!
+//! After an intended codegen change, refresh and review the diffs:
+//!
+//! ```text
+//! ZK_EMBED_BLESS=1 cargo test -p zk-embed --test snapshots
+//! git diff tools/zk-embed/tests/snapshots
+//! ```
+//!
+//! Fixtures: `demo_vk.bin` is `zk-prove-demo setup --seed 1` (compressed);
+//! `demo_vk_uncompressed.bin` is the same key serialized uncompressed; `demo_vk_bn254.bin`
+//! is the same circuit shape set up over BN254 (seed 1). `demo_proof.bin` and
+//! `demo_public_inputs.bin` are `zk-prove-demo prove` with the seed-1 key, `program_id.bin`
+//! as the capsule and `--seed 1`; the `_bn254` pair proves the BN254 shape. `registry.toml`
+//! lists one program per curve over these keys. `demo_vk_bn254.snarkjs.json` is the BN254
+//! key written out the way snarkjs `zkey export verificationkey` does. `test_sign_key.pem`
+//! is a throwaway Ed25519 key (`openssl genpkey -algorithm ed25519`).
+
+use std::{
+    env, fs,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+const PID: &str = "zkmod-attestation-program-v1";
+const VK: &str = "tests/fixtures/demo_vk.bin";
+const VK_UNCOMPRESSED: &str = "tests/fixtures/demo_vk_uncompressed.bin";
+const VK_BN254: &str = "tests/fixtures/demo_vk_bn254.bin";
+const VK_SNARKJS: &str = "tests/fixtures/demo_vk_bn254.snarkjs.json";
+const SIGN_KEY: &str = "tests/fixtures/test_sign_key.pem";
+const SIGN_PUBKEY: &str = "377bd4eaf70a0ebf5b563e1ad55c646887b59214d3f19c2f0689da393c99affc";
+
+fn manifest_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Run zk-embed from the crate root; `Ok(stdout)` on success, `Err(stderr)` otherwise.
+fn zk_embed(args: &[&str]) -> Result<String, String> {
+    zk_embed_bytes(args).map(|b| String::from_utf8(b).expect("zk-embed output is UTF-8"))
+}
+
+/// As `zk_embed`, for binary stdout
+fn zk_embed_bytes(args: &[&str]) -> Result<Vec<u8>, String> {
+    let out = Command::new(env!("CARGO_BIN_EXE_zk-embed"))
+        .current_dir(manifest_dir())
+        .args(args)
+        .output()
+        .expect("spawn zk-embed");
+    if out.status.success() {
+        Ok(out.stdout)
+    } else {
+        Err(String::from_utf8(out.stderr).expect("zk-embed output is UTF-8"))
+    }
+}
+
+/// As `zk_embed_bytes`, with `input` on stdin
+fn zk_embed_stdin(args: &[&str], input: &[u8]) -> Result<Vec<u8>, String> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_zk-embed"))
+        .current_dir(manifest_dir())
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("spawn zk-embed");
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let out = child.wait_with_output().expect("wait zk-embed");
+    if out.status.success() {
+        Ok(out.stdout)
+    } else {
+        Err(String::from_utf8(out.stderr).expect("zk-embed output is UTF-8"))
+    }
+}
+
+/// Successful run's (stdout, stderr), for reports that go to stderr beside the output
+fn zk_embed_with_stderr(args: &[&str]) -> (String, String) {
+    let out = Command::new(env!("CARGO_BIN_EXE_zk-embed"))
+        .current_dir(manifest_dir())
+        .args(args)
+        .output()
+        .expect("spawn zk-embed");
+    assert!(out.status.success(), "zk-embed failed: {}", String::from_utf8_lossy(&out.stderr));
+    (String::from_utf8(out.stdout).unwrap(), String::from_utf8(out.stderr).unwrap())
+}
+
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = manifest_dir()
+        .join("tests/snapshots")
+        .join(format!("{name}.snap"));
+    if env::var_os("ZK_EMBED_BLESS").is_some() {
+        fs::write(&path, actual).expect("write snapshot");
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "missing snapshot {}; run with ZK_EMBED_BLESS=1 to create it",
+            path.display()
+        )
+    });
+    if expected != actual {
+        let first = expected
+            .lines()
+            .zip(actual.lines())
+            .position(|(e, a)| e != a)
+            .unwrap_or_else(|| expected.lines().count().min(actual.lines().count()));
+        panic!(
+            "snapshot {name} differs at line {}\n--- expected\n{}\n+++ actual\n{}\n\
+             (ZK_EMBED_BLESS=1 to accept)",
+            first + 1,
+            expected.lines().nth(first).unwrap_or("<eof>"),
+            actual.lines().nth(first).unwrap_or("<eof>"),
+        );
+    }
+}
+
+fn snapshot_ok(name: &str, args: &[&str]) {
+    let out = zk_embed(args).unwrap_or_else(|e| panic!("{name}: zk-embed failed: {e}"));
+    assert_snapshot(name, &out);
+}
+
+fn snapshot_err(name: &str, args: &[&str]) {
+    match zk_embed(args) {
+        Ok(out) => panic!("{name}: expected failure, got:\n{out}"),
+        Err(e) => assert_snapshot(name, &e),
+    }
+}
+
+#[test]
+fn default_prefix() {
+    snapshot_ok("default_prefix", &["--program-id-str", PID, "--vk", VK]);
+}
+
+#[test]
+fn custom_prefix() {
+    snapshot_ok(
+        "custom_prefix",
+        &["--program-id-str", PID, "--vk", VK, "--const-prefix", "ATTEST_V1"],
+    );
+}
+
+#[test]
+fn prefix_is_sanitized() {
+    snapshot_ok(
+        "prefix_sanitized",
+        &["--program-id-str", PID, "--vk", VK, "--const-prefix", "attest-v1.rc"],
+    );
+}
+
+#[test]
+fn custom_domain_separator() {
+    snapshot_ok(
+        "custom_ds",
+        &["--program-id-str", PID, "--vk", VK, "--ds-program", "NONOS:ZK:PROGRAM:test"],
+    );
+}
+
+#[test]
+fn curve_bn254() {
+    snapshot_ok(
+        "curve_bn254",
+        &["--program-id-str", PID, "--vk", VK_BN254, "--curve", "bn254"],
+    );
+}
+
+#[test]
+fn lang_c_registry() {
+    snapshot_ok("lang_c_registry", &["--manifest", "tests/fixtures/registry.toml", "--lang", "c"]);
+}
+
+#[test]
+fn lang_c_needs_rust_source() {
+    snapshot_err(
+        "err_lang_c_json",
+        &["--program-id-str", PID, "--vk", VK, "--lang", "c", "--format", "json"],
+    );
+}
+
+#[test]
+fn explicit_default_curve() {
+    let default = zk_embed(&["--program-id-str", PID, "--vk", VK]).unwrap();
+    let explicit = zk_embed(&["--program-id-str", PID, "--vk", VK, "--curve", "bls12-381"]).unwrap();
+    assert_eq!(default, explicit);
+}
+
+#[test]
+fn format_json() {
+    snapshot_ok(
+        "format_json",
+        &["--program-id-str", PID, "--vk", VK, "--format", "json"],
+    );
+}
+
+#[test]
+fn format_json_escapes_strings() {
+    snapshot_ok(
+        "format_json_escaped",
+        &["--program-id-str", PID, "--vk", VK, "--format", "json", "--ds-program", "NONOS:\"ZK\"\\\tv1"],
+    );
+}
+
+#[test]
+fn format_cbor() {
+    let cbor = zk_embed_bytes(&["--program-id-str", PID, "--vk", VK, "--format", "cbor"]).unwrap();
+    assert_snapshot("format_cbor", &hex_lines(&cbor));
+}
+
+#[test]
+fn manifest_registry() {
+    snapshot_ok("manifest_registry", &["--manifest", "tests/fixtures/registry.toml"]);
+}
+
+#[test]
+fn manifest_json() {
+    snapshot_ok(
+        "manifest_json",
+        &["--manifest", "tests/fixtures/registry.toml", "--format", "json"],
+    );
+}
+
+#[test]
+fn manifest_entry_matches_single_run() {
+    let registry = zk_embed(&["--manifest", "tests/fixtures/registry.toml"]).unwrap();
+    let single = zk_embed(&["--program-id-str", PID, "--vk", VK, "--const-prefix", "ATTEST_V1"]).unwrap();
+    let consts = |s: &str| -> String {
+        let start = s.find("pub const PROGRAM_HASH_ATTEST_V1").unwrap();
+        let end = start + s[start..].find("];\n\npub const VK_").unwrap();
+        let end = end + 6 + s[end + 6..].find("];\n").unwrap();
+        s[start..end].to_string()
+    };
+    assert_eq!(consts(&registry), consts(&single));
+}
+
+#[test]
+fn manifest_rejects_duplicate_program() {
+    snapshot_err("err_manifest_dup", &["--manifest", "tests/fixtures/registry_dup.toml"]);
+}
+
+#[test]
+fn manifest_conflicts_with_single_flags() {
+    let out = zk_embed(&["--manifest", "tests/fixtures/registry.toml", "--vk", VK]);
+    assert!(out.is_err());
+}
+
+/// Hex, 32 bytes per line, so binary snapshots stay diffable
+fn hex_lines(bytes: &[u8]) -> String {
+    bytes
+        .chunks(32)
+        .map(|c| c.iter().map(|b| format!("{b:02x}")).collect::<String>() + "\n")
+        .collect()
+}
+
+/// `--emit blob` into a fresh temp dir; returns (shim on stdout, blob bytes)
+fn emit_blob(case: &str, extra: &[&str]) -> (String, Vec<u8>) {
+    let dir = env::temp_dir().join(format!("zk-embed-{case}-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("registry.blob");
+    let mut args = vec!["--emit", "blob", "--out", path.to_str().unwrap()];
+    args.extend_from_slice(extra);
+    let shim = zk_embed(&args).unwrap();
+    let blob = fs::read(&path).unwrap();
+    let _ = fs::remove_dir_all(&dir);
+    (shim, blob)
+}
+
+#[test]
+fn blob_single() {
+    let (shim, blob) = emit_blob("single", &["--program-id-str", PID, "--vk", VK]);
+    assert_snapshot("blob_single", &format!("{shim}---\n{}", hex_lines(&blob)));
+}
+
+#[test]
+fn blob_reserve_pads() {
+    let (shim, blob) = emit_blob(
+        "reserve",
+        &["--manifest", "tests/fixtures/registry.toml", "--blob-reserve", "4096"],
+    );
+    assert_eq!(blob.len(), 4096);
+    assert!(shim.contains("[u8; 4096]"));
+    let (_, unpadded) = emit_blob("unpadded", &["--manifest", "tests/fixtures/registry.toml"]);
+    assert_eq!(&blob[..unpadded.len()], &unpadded[..]);
+    assert!(blob[unpadded.len()..].iter().all(|&b| b == 0));
+}
+
+#[test]
+fn blob_needs_out() {
+    snapshot_err(
+        "err_blob_no_out",
+        &["--program-id-str", PID, "--vk", VK, "--emit", "blob"],
+    );
+}
+
+#[test]
+fn signed_registry() {
+    snapshot_ok("signed_registry", &["--manifest", "tests/fixtures/registry.toml", "--sign-key", SIGN_KEY, "--signer-pubkey", SIGN_PUBKEY]);
+}
+
+#[test]
+fn sign_key_must_match_pinned() {
+    snapshot_err(
+        "err_signer_mismatch",
+        &["--program-id-str", PID, "--vk", VK, "--sign-key", SIGN_KEY, "--signer-pubkey", &"00".repeat(32)],
+    );
+}
+
+#[test]
+fn sign_key_needs_rust_source() {
+    snapshot_err(
+        "err_sign_json",
+        &["--program-id-str", PID, "--vk", VK, "--sign-key", SIGN_KEY, "--format", "json"],
+    );
+}
+
+/// Generate a source file for `check` to audit into the temp dir
+fn generated_source(case: &str, args: &[&str]) -> String {
+    let path = env::temp_dir().join(format!("zk-embed-{case}-{}", std::process::id()));
+    let path = path.to_str().unwrap().to_string();
+    zk_embed(&[args, &["--out", &path]].concat()).unwrap();
+    path
+}
+
+#[test]
+fn check_up_to_date() {
+    let src = generated_source("check-ok", &["--program-id-str", PID, "--vk", VK]);
+    let out = zk_embed(&["check", "--source", &src, "--program-id-str", PID, "--vk", VK]);
+    let _ = fs::remove_file(&src);
+    assert_snapshot("check_ok", &out.unwrap());
+}
+
+#[test]
+fn check_reads_c_header() {
+    let src = generated_source("check-c", &["--program-id-str", PID, "--vk", VK, "--lang", "c"]);
+    let out = zk_embed(&["check", "--source", &src, "--program-id-str", PID, "--vk", VK]);
+    let _ = fs::remove_file(&src);
+    assert_snapshot("check_ok", &out.unwrap());
+}
+
+#[test]
+fn check_reports_drift() {
+    let src = generated_source("check-drift", &["--program-id-str", PID, "--vk", VK]);
+    let out = zk_embed(&["check", "--source", &src, "--program-id-str", "zkmod-other-program", "--vk", VK]);
+    let _ = fs::remove_file(&src);
+    let err = out.unwrap_err();
+    assert!(err.contains("1 of 2 constants"), "{err}");
+}
+
+#[test]
+fn vk_from_stdin() {
+    let from_file = zk_embed(&["--program-id-str", PID, "--vk", VK]).unwrap();
+    let piped = zk_embed_stdin(&["--program-id-str", PID, "--vk", "-"], &fs::read(manifest_dir().join(VK)).unwrap()).unwrap();
+    assert_eq!(from_file.as_bytes(), piped);
+}
+
+#[test]
+fn blob_to_stdout() {
+    let (_, blob) = emit_blob("stdout", &["--program-id-str", PID, "--vk", VK]);
+    let piped = zk_embed_bytes(&["--program-id-str", PID, "--vk", VK, "--emit", "blob", "--out", "-"]).unwrap();
+    assert_eq!(blob, piped);
+}
+
+#[test]
+fn size_report_over_budget() {
+    let registry = zk_embed(&["--manifest", "tests/fixtures/registry.toml"]).unwrap();
+    let (out, report) =
+        zk_embed_with_stderr(&["--manifest", "tests/fixtures/registry.toml", "--size-report", "--max-bytes", "1000"]);
+    assert_eq!(registry, out);
+    assert_snapshot("size_report", &report);
+}
+
+#[test]
+fn program_id_sources_agree() {
+    let hex: String = PID.bytes().map(|b| format!("{b:02x}")).collect();
+    let from_str = zk_embed(&["--program-id-str", PID, "--vk", VK]).unwrap();
+    let from_hex = zk_embed(&["--program-id-hex", &format!("0x{hex}"), "--vk", VK]).unwrap();
+    let from_file =
+        zk_embed(&["--program-id-file", "tests/fixtures/program_id.bin", "--vk", VK]).unwrap();
+    assert_eq!(from_str, from_hex);
+    assert_eq!(from_str, from_file);
+}
+
+#[test]
+fn uncompressed_vk_is_normalized() {
+    let compressed = zk_embed(&["--program-id-str", PID, "--vk", VK]).unwrap();
+    let uncompressed = zk_embed(&["--program-id-str", PID, "--vk", VK_UNCOMPRESSED]).unwrap();
+    assert_eq!(compressed, uncompressed);
+}
+
+#[test]
+fn snarkjs_vk_matches_arkworks() {
+    let arkworks = zk_embed(&["--program-id-str", PID, "--vk", VK_BN254, "--curve", "bn254"]).unwrap();
+    let snarkjs = zk_embed(&[
+        "--program-id-str",
+        PID,
+        "--vk",
+        VK_SNARKJS,
+        "--vk-format",
+        "snarkjs-json",
+        "--curve",
+        "bn254",
+    ])
+    .unwrap();
+    assert_eq!(arkworks, snarkjs);
+}
+
+#[test]
+fn rejects_snarkjs_vk_for_other_curve() {
+    snapshot_err(
+        "err_snarkjs_curve",
+        &["--program-id-str", PID, "--vk", VK_SNARKJS, "--vk-format", "snarkjs-json"],
+    );
+}
+
+#[test]
+fn out_file_matches_stdout() {
+    let path = env::temp_dir().join(format!("zk-embed-snap-{}.rs", std::process::id()));
+    let stdout = zk_embed(&["--program-id-str", PID, "--vk", VK]).unwrap();
+    let quiet = zk_embed(&[
+        "--program-id-str",
+        PID,
+        "--vk",
+        VK,
+        "--out",
+        path.to_str().unwrap(),
+    ])
+    .unwrap();
+    let written = fs::read_to_string(&path).unwrap();
+    let _ = fs::remove_file(&path);
+    assert!(quiet.is_empty());
+    assert_eq!(stdout, written);
+}
+
+#[test]
+fn rejects_garbage_vk() {
+    snapshot_err(
+        "err_garbage_vk",
+        &["--program-id-str", PID, "--vk", "tests/fixtures/program_id.bin"],
+    );
+}
+
+#[test]
+fn rejects_vk_for_other_curve() {
+    snapshot_err(
+        "err_wrong_curve",
+        &["--program-id-str", PID, "--vk", VK, "--curve", "bn254"],
+    );
+}
+
+#[test]
+fn verify_ok() {
+    snapshot_ok(
+        "verify_ok",
+        &[
+            "verify",
+            "--vk",
+            VK,
+            "--proof",
+            "tests/fixtures/demo_proof.bin",
+            "--public-inputs",
+            "tests/fixtures/demo_public_inputs.bin",
+        ],
+    );
+}
+
+#[test]
+fn verify_ok_bn254() {
+    snapshot_ok(
+        "verify_ok_bn254",
+        &[
+            "verify",
+            "--vk",
+            VK_BN254,
+            "--proof",
+            "tests/fixtures/demo_proof_bn254.bin",
+            "--public-inputs",
+            "tests/fixtures/demo_public_inputs_bn254.bin",
+            "--curve",
+            "bn254",
+        ],
+    );
+}
+
+#[test]
+fn gen_kat_bn254() {
+    snapshot_ok(
+        "gen_kat_bn254",
+        &[
+            "gen-kat",
+            "--vk",
+            VK_BN254,
+            "--proof",
+            "tests/fixtures/demo_proof_bn254.bin",
+            "--public-inputs",
+            "tests/fixtures/demo_public_inputs_bn254.bin",
+            "--curve",
+            "bn254",
+            "--const-prefix",
+            "attest_v1",
+        ],
+    );
+}
+
+#[test]
+fn gen_kat_rejects_failing_sample() {
+    snapshot_err(
+        "err_gen_kat_sample",
+        &[
+            "gen-kat",
+            "--vk",
+            VK,
+            "--proof",
+            "tests/fixtures/demo_proof.bin",
+            "--public-inputs",
+            "tests/fixtures/demo_public_inputs_bn254.bin",
+        ],
+    );
+}
+
+#[test]
+fn verify_rejects_wrong_inputs() {
+    snapshot_err(
+        "err_verify_mismatch",
+        &[
+            "verify",
+            "--vk",
+            VK,
+            "--proof",
+            "tests/fixtures/demo_proof.bin",
+            "--public-inputs",
+            "tests/fixtures/demo_public_inputs_bn254.bin",
+        ],
+    );
+}
+
+#[test]
+fn verify_rejects_truncated_inputs() {
+    snapshot_err(
+        "err_verify_input_len",
+        &[
+            "verify",
+            "--vk",
+            VK,
+            "--proof",
+            "tests/fixtures/demo_proof.bin",
+            "--public-inputs",
+            "tests/fixtures/program_id.bin",
+        ],
+    );
+}
+
+#[test]
+fn rejects_conflicting_program_ids() {
+    let out = zk_embed(&["--program-id-str", PID, "--program-id-hex", "00", "--vk", VK]);
+    assert!(out.is_err());
+}
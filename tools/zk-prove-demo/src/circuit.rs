@@ -0,0 +1,75 @@
+//! Toy attestation circuit and the public-input encoding shared with the bootloader.
+//!
+//! Public inputs, in order (each an Fr element):
+//!
+//! | index | value                                       |
+//! |-------|---------------------------------------------|
+//! | 0     | capsule BLAKE3 hash, bytes 0..16 (LE u128)  |
+//! | 1     | capsule BLAKE3 hash, bytes 16..32           |
+//! | 2     | PROGRAM_HASH, bytes 0..16                   |
+//! | 3     | PROGRAM_HASH, bytes 16..32                  |
+//! | 4     | key commitment (`secret * secret`)          |
+//!
+//! 128-bit limbs always fit below the BLS12-381 scalar modulus, so the encoding is injective.
+
+use ark_bls12_381::Fr;
+use ark_ff::{Field, PrimeField};
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError, Variable},
+};
+
+/// Field elements used to carry the two 32-byte digests
+pub const DIGEST_LIMBS: usize = 4;
+
+/// Total number of public inputs (digest limbs + key commitment)
+pub const NUM_INPUTS: usize = DIGEST_LIMBS + 1;
+
+/// Encode the public statement exactly as the verifier expects it.
+pub fn encode_public_inputs(capsule_hash: &[u8; 32], program_hash: &[u8; 32], key_commit: Fr) -> Vec<Fr> {
+    let mut out = Vec::with_capacity(NUM_INPUTS);
+    for digest in [capsule_hash, program_hash] {
+        for limb in digest.chunks_exact(16) {
+            out.push(Fr::from_le_bytes_mod_order(limb));
+        }
+    }
+    out.push(key_commit);
+    out
+}
+
+/// Knowledge of `secret` with `secret^2 == key_commit`, bound to a capsule and program.
+#[derive(Clone)]
+pub struct DemoCircuit {
+    pub inputs: Vec<Fr>,
+    pub secret: Fr,
+}
+
+impl DemoCircuit {
+    /// Shape-only instance for key generation; values are irrelevant to the setup.
+    pub fn blank() -> Self {
+        Self { inputs: vec![Fr::from(0u64); NUM_INPUTS], secret: Fr::from(0u64) }
+    }
+
+    pub fn new(capsule_hash: &[u8; 32], program_hash: &[u8; 32], secret: Fr) -> Self {
+        let inputs = encode_public_inputs(capsule_hash, program_hash, secret.square());
+        Self { inputs, secret }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for DemoCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let mut public = Vec::with_capacity(NUM_INPUTS);
+        for v in &self.inputs {
+            public.push(cs.new_input_variable(|| Ok(*v))?);
+        }
+        let secret = cs.new_witness_variable(|| Ok(self.secret))?;
+
+        // An input that appears in no constraint gets an identity IC point and is not bound
+        // by the proof at all; tie each digest limb in explicitly.
+        for &limb in &public[..DIGEST_LIMBS] {
+            cs.enforce_constraint(lc!() + limb, lc!() + Variable::One, lc!() + limb)?;
+        }
+        cs.enforce_constraint(lc!() + secret, lc!() + secret, lc!() + public[DIGEST_LIMBS])?;
+        Ok(())
+    }
+}
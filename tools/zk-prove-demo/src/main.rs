@@ -0,0 +1,195 @@
+//! NONOS zk-prove-demo — toy-circuit reference prover (host-side)
+//!
+//! Author: eK (team@nonos.systems) — https://nonos.systems
+//! Purpose: produce VKs, proofs and public inputs in exactly the byte formats the bootloader
+//! consumes, for end-to-end tests and as living documentation of that interface.
+//!
+//! The setup is a single-party toy ceremony with a seedable RNG. Never ship its keys.
+
+mod circuit;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use clap::{Args, Parser, Subcommand};
+use rand::{rngs::StdRng, SeedableRng};
+
+use ark_bls12_381::{Bls12_381, Fr};
+use ark_groth16::{Groth16, Proof, ProvingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+use ark_snark::SNARK;
+
+use circuit::DemoCircuit;
+
+/// Domain separator for PROGRAM_HASH derivation (must match zk-embed and the bootloader)
+const DS_PROGRAM_DEFAULT: &str = "NONOS:ZK:PROGRAM:v1";
+
+/// Output file names (inputs to zk-embed and the bootloader test fixtures)
+const PK_FILE: &str = "pk.bin";
+const VK_FILE: &str = "vk.bin";
+const PROOF_FILE: &str = "proof.bin";
+const INPUTS_FILE: &str = "public_inputs.bin";
+
+#[derive(Debug, Parser)]
+#[command(name = "zk-prove-demo", version, about = "NONOS zk-prove-demo — toy Groth16 prover emitting bootloader-format artifacts")]
+struct Cli {
+    #[command(subcommand)]
+    cmd: Cmd,
+}
+
+#[derive(Debug, Subcommand)]
+enum Cmd {
+    /// Generate a toy proving/verifying key pair (pk.bin, vk.bin)
+    Setup {
+        /// RNG seed; the same seed reproduces the same keys
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        seed: u64,
+
+        /// Directory for pk.bin and vk.bin
+        #[arg(long, value_name = "DIR", default_value = ".")]
+        out_dir: PathBuf,
+    },
+
+    /// Prove a capsule/program statement (proof.bin, public_inputs.bin)
+    Prove(ProveArgs),
+}
+
+#[derive(Debug, Args)]
+struct ProveArgs {
+    /// Proving key from `setup`
+    #[arg(long, value_name = "PATH")]
+    pk: PathBuf,
+
+    /// Capsule file whose BLAKE3 hash is bound into the public inputs
+    #[arg(long, value_name = "PATH")]
+    capsule: PathBuf,
+
+    /// Program/circuit ID as UTF-8 string
+    #[arg(long, value_name = "STR", group = "pid")]
+    program_id_str: Option<String>,
+
+    /// Program/circuit ID as hex (no 0x)
+    #[arg(long, value_name = "HEX", group = "pid")]
+    program_id_hex: Option<String>,
+
+    /// Program/circuit ID from raw bytes file
+    #[arg(long, value_name = "PATH", group = "pid")]
+    program_id_file: Option<PathBuf>,
+
+    /// Domain separator for PROGRAM_HASH
+    #[arg(long, value_name = "STR", default_value = DS_PROGRAM_DEFAULT)]
+    ds_program: String,
+
+    /// Witness: the toy secret whose square is published as the key commitment
+    #[arg(long, value_name = "N", default_value_t = 0x4e4f4e4f53)]
+    secret: u64,
+
+    /// RNG seed for proof randomization
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    seed: u64,
+
+    /// Directory for proof.bin and public_inputs.bin
+    #[arg(long, value_name = "DIR", default_value = ".")]
+    out_dir: PathBuf,
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("[x] {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    match Cli::parse().cmd {
+        Cmd::Setup { seed, out_dir } => setup(seed, &out_dir),
+        Cmd::Prove(args) => prove(&args),
+    }
+}
+
+fn setup(seed: u64, out_dir: &Path) -> Result<(), String> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (pk, vk) = Groth16::<Bls12_381>::circuit_specific_setup(DemoCircuit::blank(), &mut rng)
+        .map_err(|e| format!("setup: {e}"))?;
+
+    fs::create_dir_all(out_dir).map_err(|e| format!("create {}: {e}", out_dir.display()))?;
+    write_canonical(&out_dir.join(PK_FILE), &pk)?;
+    write_canonical(&out_dir.join(VK_FILE), &vk)?;
+
+    println!("[+] {} ({} public inputs)", out_dir.join(VK_FILE).display(), circuit::NUM_INPUTS);
+    println!("[+] {}", out_dir.join(PK_FILE).display());
+    Ok(())
+}
+
+fn prove(args: &ProveArgs) -> Result<(), String> {
+    let pk_raw = fs::read(&args.pk).map_err(|e| format!("read proving key {}: {e}", args.pk.display()))?;
+    let pk = ProvingKey::<Bls12_381>::deserialize_with_mode(pk_raw.as_slice(), Compress::Yes, Validate::Yes)
+        .map_err(|_| "failed to deserialize proving key (expected zk-prove-demo setup output)".to_string())?;
+
+    let capsule = fs::read(&args.capsule).map_err(|e| format!("read capsule {}: {e}", args.capsule.display()))?;
+    let capsule_hash = *blake3::hash(&capsule).as_bytes();
+    let program_hash = derive_program_hash(&args.ds_program, &load_program_id_bytes(args)?);
+
+    let circuit = DemoCircuit::new(&capsule_hash, &program_hash, Fr::from(args.secret));
+    let inputs = circuit.inputs.clone();
+
+    let mut rng = StdRng::seed_from_u64(args.seed);
+    let proof = Groth16::<Bls12_381>::prove(&pk, circuit, &mut rng).map_err(|e| format!("prove: {e}"))?;
+
+    // Never hand out a proof we would reject ourselves
+    let ok = Groth16::<Bls12_381>::verify(&pk.vk, &inputs, &proof).map_err(|e| format!("verify: {e}"))?;
+    if !ok {
+        return Err("self-check failed: generated proof does not verify".into());
+    }
+
+    fs::create_dir_all(&args.out_dir).map_err(|e| format!("create {}: {e}", args.out_dir.display()))?;
+    write_canonical::<Proof<Bls12_381>>(&args.out_dir.join(PROOF_FILE), &proof)?;
+    write_inputs(&args.out_dir.join(INPUTS_FILE), &inputs)?;
+
+    println!("capsule_hash = {}", hex::encode(capsule_hash));
+    println!("program_hash = {}", hex::encode(program_hash));
+    println!("[+] {}", args.out_dir.join(PROOF_FILE).display());
+    println!("[+] {} ({} x 32 bytes)", args.out_dir.join(INPUTS_FILE).display(), inputs.len());
+    Ok(())
+}
+
+/* ---------------- helpers ---------------- */
+
+fn load_program_id_bytes(args: &ProveArgs) -> Result<Vec<u8>, String> {
+    match (&args.program_id_str, &args.program_id_hex, &args.program_id_file) {
+        (Some(s), None, None) => Ok(s.as_bytes().to_vec()),
+        (None, Some(h), None) => {
+            let h = h.trim().trim_start_matches("0x").trim_start_matches("0X");
+            hex::decode(h).map_err(|e| format!("program-id-hex: {e}"))
+        }
+        (None, None, Some(p)) => fs::read(p).map_err(|e| format!("read program-id-file {}: {e}", p.display())),
+        _ => Err("provide exactly one of --program-id-str | --program-id-hex | --program-id-file".into()),
+    }
+}
+
+fn derive_program_hash(ds_program: &str, program_id_bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new_derive_key(ds_program);
+    hasher.update(program_id_bytes);
+    *hasher.finalize().as_bytes()
+}
+
+/// Arkworks canonical compressed form — the same encoding zk-embed normalizes VKs to.
+fn write_canonical<T: CanonicalSerialize>(path: &Path, value: &T) -> Result<(), String> {
+    let mut out = Vec::new();
+    value
+        .serialize_with_mode(&mut out, Compress::Yes)
+        .map_err(|e| format!("serialize {}: {e}", path.display()))?;
+    fs::write(path, out).map_err(|e| format!("write {}: {e}", path.display()))
+}
+
+/// Public inputs as a bare concatenation of 32-byte little-endian Fr encodings (no length prefix).
+fn write_inputs(path: &Path, inputs: &[Fr]) -> Result<(), String> {
+    let mut out = Vec::with_capacity(inputs.len() * 32);
+    for x in inputs {
+        x.serialize_compressed(&mut out)
+            .map_err(|e| format!("serialize public input: {e}"))?;
+    }
+    fs::write(path, out).map_err(|e| format!("write {}: {e}", path.display()))
+}
@@ -0,0 +1,364 @@
+//! `cargo xtask bench-boot` — repeated boots across QEMU configurations, per-phase timings.
+//!
+//! Phase boundaries come from structured serial records (see `serial.rs`): a phase starts at
+//! its first record and ends where the next phase starts, or at the scenario's `until`
+//! marker for the last one. `firmware` is everything before the first record. Times use the
+//! host clock at line arrival, so compare runs on the same host only.
+
+use std::{collections::BTreeMap, fmt::Write as _, fs, path::PathBuf, time::Duration};
+
+use crate::{
+    qemu::{self, Firmware, Machine},
+    scenario::Scenario,
+    secure_boot::SecureBoot,
+    serial,
+    test_boot::{BootInputs, DEFAULT_SCENARIO},
+    Flags,
+};
+
+/// Regressions smaller than this are noise on any host we run on
+const NOISE_FLOOR_MS: f64 = 5.0;
+
+struct Config {
+    name: String,
+    cpus: u32,
+    memory_mb: u32,
+    secure_boot: bool,
+}
+
+/// Per-phase samples for one configuration (phase → ms per run)
+#[derive(Default)]
+struct Samples {
+    phases: BTreeMap<String, Vec<f64>>,
+    order: Vec<String>,
+}
+
+impl Samples {
+    fn add(&mut self, phase: &str, ms: f64) {
+        if !self.phases.contains_key(phase) {
+            self.order.push(phase.to_string());
+        }
+        self.phases.entry(phase.to_string()).or_default().push(ms);
+    }
+}
+
+pub(crate) fn run(raw: &[String]) -> Result<(), String> {
+    let switches = [BootInputs::SWITCHES, SecureBoot::SWITCHES].concat();
+    let mut f = Flags::parse(raw, &switches)?;
+    let inputs = BootInputs::from_flags(&mut f)?;
+    let sb = SecureBoot::from_flags(&mut f)?;
+    let runs = parse_num(f.value("runs").as_deref().unwrap_or("3"), "--runs")?;
+    let cpus = parse_list(f.value("cpus").as_deref().unwrap_or("1,4"), "--cpus")?;
+    let memory = parse_list(f.value("memory").as_deref().unwrap_or("512"), "--memory")?;
+    let threshold = f
+        .value("threshold")
+        .map(|t| {
+            t.parse::<f64>()
+                .map_err(|_| format!("bad --threshold `{t}`"))
+        })
+        .transpose()?
+        .unwrap_or(10.0);
+    let baseline = f.value("baseline").map(PathBuf::from);
+    let out_dir = f
+        .value("out")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| crate::xtask_dir().join("bench"));
+    let scenario = f
+        .value("scenario")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| crate::workspace_root().join(DEFAULT_SCENARIO));
+    let firmware = Firmware::locate(inputs.arch, false, f.value("ovmf"), f.value("ovmf-vars"))?;
+    let sb_ovmf = f.value("sb-ovmf");
+    let sb_vars = f.value("sb-vars");
+    f.finish()?;
+    if sb.is_none() && (sb_ovmf.is_some() || sb_vars.is_some()) {
+        return Err("--sb-ovmf/--sb-vars only apply with --secure-boot".into());
+    }
+
+    let sc = Scenario::load(&scenario)?;
+    let until = sc
+        .until
+        .as_deref()
+        .ok_or("bench scenario needs an `until` marker to know when boot is done")?;
+
+    let mut configs = Vec::new();
+    for secure_boot in [false, true] {
+        if secure_boot && sb.is_none() {
+            continue;
+        }
+        for &c in &cpus {
+            for &m in &memory {
+                configs.push(Config {
+                    name: format!("{c}cpu-{m}M-sb{}", if secure_boot { "on" } else { "off" }),
+                    cpus: c,
+                    memory_mb: m,
+                    secure_boot,
+                });
+            }
+        }
+    }
+
+    let work = crate::xtask_dir().join("bench-boot");
+    let efi = inputs.loader()?;
+    let disk = inputs.prepare(&efi, &work, None)?;
+    // The sbon variants boot the loader signed with the test db key against enrolled vars,
+    // exactly as `test-boot --secure-boot` does
+    let sb_boot = match &sb {
+        Some(sb) => {
+            sb.ensure_keys()?;
+            let firmware = Firmware::locate(inputs.arch, true, sb_ovmf, sb_vars)?;
+            let sb_work = work.join("sb");
+            Some((
+                sb.enrolled(&firmware, &sb_work)?,
+                inputs.prepare(&efi, &sb_work, Some(sb))?,
+            ))
+        }
+        None => None,
+    };
+
+    let mut results: Vec<(String, Samples)> = Vec::new();
+    for cfg in &configs {
+        let (firmware, disk) = match (&sb_boot, cfg.secure_boot) {
+            (Some((fw, disk)), true) => (fw.clone(), disk.clone()),
+            _ => (firmware.clone(), disk.clone()),
+        };
+        let machine = Machine {
+            arch: inputs.arch,
+            qemu: inputs.qemu.clone(),
+            firmware,
+            disk,
+            memory_mb: cfg.memory_mb,
+            cpus: cfg.cpus,
+            secure_boot: cfg.secure_boot,
+        };
+
+        let mut samples = Samples::default();
+        for i in 0..runs {
+            println!("[*] {} run {}/{runs}", cfg.name, i + 1);
+            let log = qemu::boot(
+                &machine,
+                &work.join(&cfg.name),
+                sc.timeout,
+                Some(until),
+                false,
+            )?;
+            let failures = sc.check(&log.lines);
+            if !log.reached_marker || !failures.is_empty() {
+                let why = failures
+                    .first()
+                    .cloned()
+                    .unwrap_or_else(|| format!("`{until}` not reached"));
+                return Err(format!("{} run {}: boot failed ({why})", cfg.name, i + 1));
+            }
+            // A timing labelled sbon must come from a boot that actually enforced it
+            if cfg.secure_boot && !sb_enforced(&log) {
+                return Err(format!(
+                    "{} run {}: Secure Boot not reported enforced (no {}I|secureboot|SB_ENFORCED| record)",
+                    cfg.name,
+                    i + 1,
+                    serial::PREFIX
+                ));
+            }
+            for (phase, ms) in phase_times(&log) {
+                samples.add(&phase, ms);
+            }
+        }
+        results.push((cfg.name.clone(), samples));
+    }
+
+    fs::create_dir_all(&out_dir).map_err(|e| format!("create {}: {e}", out_dir.display()))?;
+    let tsv = out_dir.join("report.tsv");
+    let json = out_dir.join("report.json");
+    fs::write(&tsv, to_tsv(&results)).map_err(|e| format!("write {}: {e}", tsv.display()))?;
+    fs::write(&json, to_json(&results)).map_err(|e| format!("write {}: {e}", json.display()))?;
+    print_table(&results);
+    println!("[+] report: {} / {}", tsv.display(), json.display());
+
+    match baseline {
+        Some(path) => compare(&results, &path, threshold),
+        None => Ok(()),
+    }
+}
+
+fn sb_enforced(log: &qemu::BootLog) -> bool {
+    serial::parse_all(&log.lines)
+        .iter()
+        .any(|r| r.phase == "secureboot" && r.code == "SB_ENFORCED")
+}
+
+/// (phase, ms) segments of one boot, in boot order; repeated phases accumulate.
+fn phase_times(log: &qemu::BootLog) -> Vec<(String, f64)> {
+    let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+    let end = log.stamps.last().copied().unwrap_or_default();
+
+    let mut marks: Vec<(String, Duration)> = Vec::new();
+    for r in serial::parse_all(&log.lines) {
+        let at = log.stamps[r.line - 1];
+        if marks.last().map(|(p, _)| p != &r.phase).unwrap_or(true) {
+            marks.push((r.phase, at));
+        }
+    }
+
+    let mut out: Vec<(String, f64)> = Vec::new();
+    let first = marks.first().map(|(_, t)| *t).unwrap_or(end);
+    out.push(("firmware".to_string(), ms(first)));
+    for (i, (phase, start)) in marks.iter().enumerate() {
+        let stop = marks.get(i + 1).map(|(_, t)| *t).unwrap_or(end);
+        let d = ms(stop.saturating_sub(*start));
+        match out.iter_mut().find(|(p, _)| p == phase) {
+            Some((_, acc)) => *acc += d,
+            None => out.push((phase.clone(), d)),
+        }
+    }
+    out.push(("total".to_string(), ms(end)));
+    out
+}
+
+fn median(v: &[f64]) -> f64 {
+    let mut s = v.to_vec();
+    s.sort_by(f64::total_cmp);
+    let n = s.len();
+    if n == 0 {
+        0.0
+    } else if n % 2 == 1 {
+        s[n / 2]
+    } else {
+        (s[n / 2 - 1] + s[n / 2]) / 2.0
+    }
+}
+
+fn min_max(v: &[f64]) -> (f64, f64) {
+    v.iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &x| {
+            (lo.min(x), hi.max(x))
+        })
+}
+
+fn to_tsv(results: &[(String, Samples)]) -> String {
+    let mut out = String::from("# config\tphase\tmedian_ms\tmin_ms\tmax_ms\truns\n");
+    for (cfg, s) in results {
+        for phase in &s.order {
+            let v = &s.phases[phase];
+            let (lo, hi) = min_max(v);
+            let _ = writeln!(
+                out,
+                "{cfg}\t{phase}\t{:.1}\t{lo:.1}\t{hi:.1}\t{}",
+                median(v),
+                v.len()
+            );
+        }
+    }
+    out
+}
+
+fn to_json(results: &[(String, Samples)]) -> String {
+    let mut out = String::from("{\n  \"configs\": [\n");
+    for (ci, (cfg, s)) in results.iter().enumerate() {
+        // Phase names come from the loader's serial output; never trust them to be JSON-safe
+        let _ = writeln!(
+            out,
+            "    {{\"config\": {}, \"phases\": [",
+            serial::json_str(cfg)
+        );
+        for (pi, phase) in s.order.iter().enumerate() {
+            let v = &s.phases[phase];
+            let (lo, hi) = min_max(v);
+            let _ = write!(
+                out,
+                "      {{\"phase\": {}, \"median_ms\": {:.1}, \"min_ms\": {lo:.1}, \"max_ms\": {hi:.1}, \"runs\": {}}}",
+                serial::json_str(phase),
+                median(v),
+                v.len()
+            );
+            out.push_str(if pi + 1 == s.order.len() { "\n" } else { ",\n" });
+        }
+        out.push_str(if ci + 1 == results.len() {
+            "    ]}\n"
+        } else {
+            "    ]},\n"
+        });
+    }
+    out.push_str("  ]\n}\n");
+    out
+}
+
+fn print_table(results: &[(String, Samples)]) {
+    println!();
+    println!(
+        "{:<20} {:<14} {:>10} {:>10} {:>10}",
+        "config", "phase", "median ms", "min", "max"
+    );
+    for (cfg, s) in results {
+        for phase in &s.order {
+            let v = &s.phases[phase];
+            let (lo, hi) = min_max(v);
+            println!(
+                "{cfg:<20} {phase:<14} {:>10.1} {lo:>10.1} {hi:>10.1}",
+                median(v)
+            );
+        }
+    }
+    println!();
+}
+
+/// Fail when any (config, phase) median grew by more than `threshold` percent.
+fn compare(
+    results: &[(String, Samples)],
+    baseline: &std::path::Path,
+    threshold: f64,
+) -> Result<(), String> {
+    let text =
+        fs::read_to_string(baseline).map_err(|e| format!("read {}: {e}", baseline.display()))?;
+    let mut base = BTreeMap::new();
+    for line in text
+        .lines()
+        .filter(|l| !l.starts_with('#') && !l.trim().is_empty())
+    {
+        let cols: Vec<&str> = line.split('\t').collect();
+        if let [cfg, phase, median, ..] = cols.as_slice() {
+            if let Ok(m) = median.parse::<f64>() {
+                base.insert((cfg.to_string(), phase.to_string()), m);
+            }
+        }
+    }
+
+    let mut regressions = Vec::new();
+    for (cfg, s) in results {
+        for phase in &s.order {
+            let Some(&was) = base.get(&(cfg.clone(), phase.clone())) else {
+                continue;
+            };
+            let now = median(&s.phases[phase]);
+            if now - was > NOISE_FLOOR_MS && now > was * (1.0 + threshold / 100.0) {
+                regressions.push(format!("{cfg}/{phase}: {was:.1} ms -> {now:.1} ms"));
+            }
+        }
+    }
+
+    if regressions.is_empty() {
+        println!(
+            "[+] no regressions against {} (threshold {threshold}%)",
+            baseline.display()
+        );
+        Ok(())
+    } else {
+        for r in &regressions {
+            println!("[-] {r}");
+        }
+        Err(format!(
+            "{} phase(s) regressed more than {threshold}%",
+            regressions.len()
+        ))
+    }
+}
+
+fn parse_num(s: &str, flag: &str) -> Result<u32, String> {
+    match s.parse::<u32>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("bad {flag} `{s}`")),
+    }
+}
+
+fn parse_list(s: &str, flag: &str) -> Result<Vec<u32>, String> {
+    s.split(',').map(|v| parse_num(v.trim(), flag)).collect()
+}
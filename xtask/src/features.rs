@@ -0,0 +1,252 @@
+//! `cargo xtask features` — check the feature-combination rules against the manifest.
+//!
+//! The rules live in `build/feature_rules.rs`; build.rs enforces them on every loader
+//! build. This command makes sure they still name real features, that the Makefile
+//! presets obey them (`RELEASE_FEATURES` under the release-only rules too), and prints the
+//! verdict for every single feature and pair. With `--build` it runs `cargo check` on each
+//! combination and confirms the build script accepts exactly the combinations the rules
+//! allow. `--release` judges the matrix (and checks it) as a release build.
+
+use std::{fs, path::Path};
+
+use crate::{Arch, Flags};
+
+include!("../../build/feature_rules.rs");
+
+/// Makefile variables holding feature presets
+const PRESETS: &[&str] = &["FEATURES", "RELEASE_FEATURES", "DEV_FEATURES"];
+
+/// The preset `make release` builds with, checked under the release-only rules
+const RELEASE_PRESET: &str = "RELEASE_FEATURES";
+
+/// Marker build.rs prints when it rejects a combination
+const REJECTED: &str = "invalid feature combination";
+
+pub(crate) fn run(raw: &[String]) -> Result<(), String> {
+    let mut f = Flags::parse(raw, &["build", "release"])?;
+    let build = f.switch("build");
+    let release = f.switch("release");
+    let arch = Arch::from_flags(&mut f)?;
+    let only = f.value("features");
+    f.finish()?;
+
+    let root = crate::workspace_root();
+    let (known, defaults) = manifest_features(&root.join("Cargo.toml"))?;
+    let mut problems = Vec::new();
+
+    for name in FEATURE_CONFLICTS
+        .iter()
+        .chain(FEATURE_REQUIRES)
+        .chain(FEATURE_RELEASE_REQUIRES)
+        .flat_map(|(a, b, _)| [*a, *b])
+    {
+        if !known.iter().any(|k| k == name) {
+            problems.push(format!(
+                "build/feature_rules.rs: `{name}` is not a feature in Cargo.toml"
+            ));
+        }
+    }
+
+    if let Some(list) = only {
+        let set = resolve(&list, &defaults);
+        problems.extend(unknown(&set, &known, "--features"));
+        problems.extend(feature_violations(&as_strs(&set), release));
+        if problems.is_empty() {
+            println!("[+] {} is a valid combination", set.join(","));
+        }
+        return finish(problems);
+    }
+
+    for (var, list) in makefile_presets(&root.join("Makefile"))? {
+        let set = resolve(&list, &defaults);
+        let mut bad = unknown(&set, &known, &format!("Makefile {var}"));
+        bad.extend(
+            feature_violations(&as_strs(&set), release || var == RELEASE_PRESET)
+                .into_iter()
+                .map(|v| format!("Makefile {var}: {v}")),
+        );
+        if bad.is_empty() {
+            println!("[+] Makefile {var} = {}", set.join(","));
+        } else {
+            println!("[-] Makefile {var} = {}", set.join(","));
+        }
+        problems.extend(bad);
+    }
+
+    // Every single optional feature and every pair, on top of the defaults
+    let optional: Vec<&String> = known.iter().filter(|k| !defaults.contains(k)).collect();
+    let mut combos: Vec<Vec<String>> = optional.iter().map(|a| vec![a.to_string()]).collect();
+    for (i, a) in optional.iter().enumerate() {
+        for b in &optional[i + 1..] {
+            combos.push(vec![a.to_string(), b.to_string()]);
+        }
+    }
+
+    println!();
+    println!("{:<40} verdict", "features (+ default)");
+    let mut mismatched = 0usize;
+    for combo in &combos {
+        let mut set = defaults.clone();
+        set.extend(combo.iter().cloned());
+        let v = feature_violations(&as_strs(&set), release);
+        let verdict = if v.is_empty() { "ok" } else { "rejected" };
+        let mut line = format!("{:<40} {verdict}", combo.join(","));
+        if build {
+            let accepted = cargo_check(arch, &combo.join(","), release)?;
+            if accepted != v.is_empty() {
+                mismatched += 1;
+                line.push_str(if accepted {
+                    "  MISMATCH: build accepted it"
+                } else {
+                    "  MISMATCH: build rejected it"
+                });
+            } else {
+                line.push_str("  (build agrees)");
+            }
+        }
+        println!("{line}");
+        for why in v {
+            println!("    {why}");
+        }
+    }
+    if mismatched > 0 {
+        problems.push(format!(
+            "{mismatched} combination(s) where build.rs disagrees with the rules"
+        ));
+    }
+    finish(problems)
+}
+
+fn finish(problems: Vec<String>) -> Result<(), String> {
+    if problems.is_empty() {
+        return Ok(());
+    }
+    println!();
+    for p in &problems {
+        println!("[-] {p}");
+    }
+    Err(format!("{} feature problem(s)", problems.len()))
+}
+
+/// (every feature name except `default`, the features `default` enables)
+fn manifest_features(path: &Path) -> Result<(Vec<String>, Vec<String>), String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("read {}: {e}", path.display()))?;
+    let mut known = Vec::new();
+    let mut defaults = Vec::new();
+    let mut in_features = false;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_features = line == "[features]";
+            continue;
+        }
+        if !in_features || line.starts_with('#') {
+            continue;
+        }
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        let name = name.trim();
+        if name == "default" {
+            defaults = value
+                .trim()
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .split(',')
+                .map(|f| f.trim().trim_matches('"').to_string())
+                .filter(|f| !f.is_empty())
+                .collect();
+        } else {
+            known.push(name.to_string());
+        }
+    }
+    if known.is_empty() {
+        return Err(format!("{}: no [features] found", path.display()));
+    }
+    Ok((known, defaults))
+}
+
+/// `NAME := value` feature presets, with `$(FEATURES)` expanded
+fn makefile_presets(path: &Path) -> Result<Vec<(String, String)>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("read {}: {e}", path.display()))?;
+    let mut out: Vec<(String, String)> = Vec::new();
+    for line in text.lines() {
+        let Some((var, value)) = line.split_once(":=") else {
+            continue;
+        };
+        let var = var.trim();
+        if !PRESETS.contains(&var) {
+            continue;
+        }
+        let mut value = value.trim().to_string();
+        if let Some((_, base)) = out.iter().find(|(v, _)| v == "FEATURES") {
+            value = value.replace("$(FEATURES)", base);
+        }
+        out.push((var.to_string(), value));
+    }
+    Ok(out)
+}
+
+/// Feature list as cargo sees it: `default` expanded, duplicates dropped
+fn resolve(list: &str, defaults: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for f in list
+        .split([',', ' '])
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+    {
+        let names = if f == "default" {
+            defaults.to_vec()
+        } else {
+            vec![f.to_string()]
+        };
+        for n in names {
+            if !out.contains(&n) {
+                out.push(n);
+            }
+        }
+    }
+    out
+}
+
+fn unknown(set: &[String], known: &[String], what: &str) -> Vec<String> {
+    set.iter()
+        .filter(|f| !known.contains(f))
+        .map(|f| format!("{what}: `{f}` is not a feature in Cargo.toml"))
+        .collect()
+}
+
+fn as_strs(set: &[String]) -> Vec<&str> {
+    set.iter().map(String::as_str).collect()
+}
+
+/// Whether build.rs accepts the combination; other build failures are errors.
+fn cargo_check(arch: Arch, features: &str, release: bool) -> Result<bool, String> {
+    let mut cmd = crate::cargo();
+    cmd.current_dir(crate::workspace_root())
+        .args([
+            "check",
+            "--package",
+            "nonos_boot",
+            "--target",
+            arch.uefi_target(),
+        ])
+        .args(["--features", features]);
+    if release {
+        cmd.arg("--release");
+    }
+    let out = cmd
+        .output()
+        .map_err(|e| format!("spawn cargo check: {e}"))?;
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    if out.status.success() {
+        Ok(true)
+    } else if stderr.contains(REJECTED) {
+        Ok(false)
+    } else {
+        Err(format!(
+            "cargo check --features {features} failed for another reason:\n{}",
+            stderr.trim()
+        ))
+    }
+}
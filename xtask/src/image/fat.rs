@@ -0,0 +1,459 @@
+//! FAT32 volume writer (512-byte sectors, long file names, deterministic timestamps).
+
+use std::collections::BTreeMap;
+
+use super::SECTOR;
+
+const RESERVED_SECTORS: u64 = 32;
+const NUM_FATS: u64 = 2;
+const FSINFO_SECTOR: u64 = 1;
+const BACKUP_BOOT_SECTOR: u64 = 6;
+const MIN_CLUSTERS: u64 = 65_525;
+const MAX_CLUSTERS: u64 = 0x0FFF_FFF4;
+const DIR_ENTRY: usize = 32;
+const LFN_CHARS: usize = 13;
+const EOC: u32 = 0x0FFF_FFFF;
+
+const ATTR_VOLUME_ID: u8 = 0x08;
+const ATTR_DIRECTORY: u8 = 0x10;
+const ATTR_ARCHIVE: u8 = 0x20;
+const ATTR_LFN: u8 = 0x0F;
+
+/// 1980-01-01 00:00:00; fixed so identical inputs give identical images
+const DOS_DATE: u16 = (1 << 5) | 1;
+const DOS_TIME: u16 = 0;
+
+enum Kind {
+    Dir(Vec<usize>),
+    File(Vec<u8>),
+}
+
+struct Node {
+    name: String,
+    short: [u8; 11],
+    kind: Kind,
+    parent: usize,
+    cluster: u32,
+    clusters: u32,
+}
+
+/// Volume parameters derived from its size
+struct Geometry {
+    sectors: u64,
+    spc: u64,
+    fat_sectors: u64,
+    clusters: u64,
+}
+
+impl Geometry {
+    fn new(sectors: u64) -> Result<Self, String> {
+        let bytes = sectors * SECTOR;
+        // Microsoft's recommended cluster sizes for FAT32
+        let spc = match bytes {
+            b if b <= 260 << 20 => 1,
+            b if b <= 8 << 30 => 8,
+            b if b <= 16 << 30 => 16,
+            b if b <= 32 << 30 => 32,
+            _ => 64,
+        };
+
+        let mut fat_sectors = 1;
+        let clusters = loop {
+            let data = sectors
+                .checked_sub(RESERVED_SECTORS + NUM_FATS * fat_sectors)
+                .ok_or("ESP too small")?;
+            let clusters = data / spc;
+            let need = ((clusters + 2) * 4).div_ceil(SECTOR);
+            if need <= fat_sectors {
+                break clusters;
+            }
+            fat_sectors = need;
+        };
+
+        if clusters < MIN_CLUSTERS {
+            return Err(format!(
+                "ESP of {} MiB is too small for FAT32 (need at least 33 MiB)",
+                bytes >> 20
+            ));
+        }
+        if clusters > MAX_CLUSTERS {
+            return Err("ESP too large for FAT32".into());
+        }
+        Ok(Self {
+            sectors,
+            spc,
+            fat_sectors,
+            clusters,
+        })
+    }
+
+    fn cluster_bytes(&self) -> u64 {
+        self.spc * SECTOR
+    }
+
+    /// Byte offset of `cluster` from the start of the volume
+    fn offset(&self, cluster: u32) -> u64 {
+        let data_start = RESERVED_SECTORS + NUM_FATS * self.fat_sectors;
+        (data_start + (cluster as u64 - 2) * self.spc) * SECTOR
+    }
+}
+
+/// Lay out a FAT32 volume holding `files` (ESP path → contents).
+///
+/// Returns (offset within the volume, bytes) regions; everything else is zero.
+pub(crate) fn build(
+    sectors: u64,
+    hidden_sectors: u64,
+    label: &str,
+    volume_id: u32,
+    files: &[(String, Vec<u8>)],
+) -> Result<Vec<(u64, Vec<u8>)>, String> {
+    let geo = Geometry::new(sectors)?;
+    let mut nodes = tree(files)?;
+
+    // Directory sizes depend only on names, so cluster allocation is a single pass
+    let mut next = 2u32;
+    for i in 0..nodes.len() {
+        let bytes = match &nodes[i].kind {
+            Kind::Dir(children) => {
+                let fixed = if i == 0 { 1 } else { 2 }; // volume label vs. `.` + `..`
+                let entries: usize = children.iter().map(|&c| lfn_count(&nodes[c]) + 1).sum();
+                ((fixed + entries) * DIR_ENTRY).max(1) as u64
+            }
+            Kind::File(data) => data.len() as u64,
+        };
+        let clusters = bytes.div_ceil(geo.cluster_bytes()) as u32;
+        if clusters > 0 {
+            nodes[i].cluster = next;
+            nodes[i].clusters = clusters;
+            next += clusters;
+        }
+    }
+    let used = next as u64 - 2;
+    if used > geo.clusters {
+        return Err(format!(
+            "ESP too small: contents need {} KiB",
+            used * geo.cluster_bytes() / 1024
+        ));
+    }
+
+    let mut fat = vec![0u32; geo.clusters as usize + 2];
+    fat[0] = 0x0FFF_FFF8;
+    fat[1] = EOC;
+    for n in &nodes {
+        for k in 0..n.clusters {
+            let c = (n.cluster + k) as usize;
+            fat[c] = if k + 1 == n.clusters {
+                EOC
+            } else {
+                c as u32 + 1
+            };
+        }
+    }
+    let mut fat_bytes = Vec::with_capacity(fat.len() * 4);
+    for e in &fat {
+        fat_bytes.extend_from_slice(&e.to_le_bytes());
+    }
+
+    let boot = boot_sector(&geo, hidden_sectors, label, volume_id);
+    let info = fsinfo(geo.clusters - used, next);
+
+    let mut out = vec![
+        (0, boot.clone()),
+        (FSINFO_SECTOR * SECTOR, info.clone()),
+        (BACKUP_BOOT_SECTOR * SECTOR, boot),
+        ((BACKUP_BOOT_SECTOR + FSINFO_SECTOR) * SECTOR, info),
+    ];
+    for f in 0..NUM_FATS {
+        out.push((
+            (RESERVED_SECTORS + f * geo.fat_sectors) * SECTOR,
+            fat_bytes.clone(),
+        ));
+    }
+    for (i, n) in nodes.iter().enumerate() {
+        if n.clusters == 0 {
+            continue;
+        }
+        let data = match &n.kind {
+            Kind::Dir(children) => dir_bytes(&nodes, i, children, label, geo.cluster_bytes()),
+            Kind::File(data) => data.clone(),
+        };
+        out.push((geo.offset(n.cluster), data));
+    }
+    Ok(out)
+}
+
+/// Flatten ESP paths into a node arena; index 0 is the root, parents precede children.
+fn tree(files: &[(String, Vec<u8>)]) -> Result<Vec<Node>, String> {
+    // BTreeMap keeps directory order (and thus the image) independent of input order
+    #[derive(Default)]
+    struct Dir {
+        dirs: BTreeMap<String, Dir>,
+        files: BTreeMap<String, Vec<u8>>,
+    }
+
+    let mut root = Dir::default();
+    for (path, data) in files {
+        let mut parts: Vec<&str> = path.split('/').collect();
+        let leaf = parts.pop().ok_or("empty path")?;
+        let mut dir = &mut root;
+        for p in parts {
+            if dir.files.contains_key(p) {
+                return Err(format!("`{path}`: `{p}` is a file"));
+            }
+            dir = dir.dirs.entry(p.to_string()).or_default();
+        }
+        if dir.dirs.contains_key(leaf) || dir.files.insert(leaf.to_string(), data.clone()).is_some()
+        {
+            return Err(format!("`{path}` listed twice"));
+        }
+    }
+
+    fn push(nodes: &mut Vec<Node>, name: String, parent: usize, kind: Kind) -> usize {
+        nodes.push(Node {
+            name,
+            short: [b' '; 11],
+            kind,
+            parent,
+            cluster: 0,
+            clusters: 0,
+        });
+        nodes.len() - 1
+    }
+
+    fn walk(nodes: &mut Vec<Node>, me: usize, dir: Dir) -> Result<(), String> {
+        let mut children = Vec::new();
+        let mut subdirs = Vec::new();
+        for (name, sub) in dir.dirs {
+            let i = push(nodes, name, me, Kind::Dir(Vec::new()));
+            children.push(i);
+            subdirs.push((i, sub));
+        }
+        for (name, data) in dir.files {
+            children.push(push(nodes, name, me, Kind::File(data)));
+        }
+
+        let mut taken: Vec<[u8; 11]> = Vec::new();
+        for &c in &children {
+            let short = short_name(&nodes[c].name, &taken)?;
+            taken.push(short);
+            nodes[c].short = short;
+        }
+        nodes[me].kind = Kind::Dir(children);
+
+        for (i, sub) in subdirs {
+            walk(nodes, i, sub)?;
+        }
+        Ok(())
+    }
+
+    let mut nodes = Vec::new();
+    push(&mut nodes, String::new(), 0, Kind::Dir(Vec::new()));
+    walk(&mut nodes, 0, root)?;
+    Ok(nodes)
+}
+
+fn is_short_char(b: u8) -> bool {
+    b.is_ascii_uppercase() || b.is_ascii_digit() || b"!#$%&'()-@^_`{}~".contains(&b)
+}
+
+/// The name is already a valid upper-case 8.3 name (no LFN entries needed)
+fn exact_short(name: &str) -> Option<[u8; 11]> {
+    let (base, ext) = name.split_once('.').unwrap_or((name, ""));
+    if base.is_empty() || base.len() > 8 || ext.len() > 3 || ext.contains('.') {
+        return None;
+    }
+    if !base.bytes().chain(ext.bytes()).all(is_short_char) {
+        return None;
+    }
+    let mut out = [b' '; 11];
+    out[..base.len()].copy_from_slice(base.as_bytes());
+    out[8..8 + ext.len()].copy_from_slice(ext.as_bytes());
+    Some(out)
+}
+
+fn short_name(name: &str, taken: &[[u8; 11]]) -> Result<[u8; 11], String> {
+    if let Some(s) = exact_short(name) {
+        if taken.contains(&s) {
+            return Err(format!(
+                "`{name}` collides with another entry (FAT is case-insensitive)"
+            ));
+        }
+        return Ok(s);
+    }
+
+    let clean = |s: &str| -> Vec<u8> {
+        s.bytes()
+            .map(|b| b.to_ascii_uppercase())
+            .filter(|b| *b != b' ' && *b != b'.')
+            .map(|b| if is_short_char(b) { b } else { b'_' })
+            .collect()
+    };
+    let (base, ext) = match name.rfind('.') {
+        Some(i) if i > 0 => (clean(&name[..i]), clean(&name[i + 1..])),
+        _ => (clean(name), Vec::new()),
+    };
+    let base = if base.is_empty() { b"_".to_vec() } else { base };
+
+    for n in 1..1_000_000u32 {
+        let tail = format!("~{n}");
+        let keep = base.len().min(8 - tail.len());
+        let mut out = [b' '; 11];
+        out[..keep].copy_from_slice(&base[..keep]);
+        out[keep..keep + tail.len()].copy_from_slice(tail.as_bytes());
+        let e = ext.len().min(3);
+        out[8..8 + e].copy_from_slice(&ext[..e]);
+        if !taken.contains(&out) {
+            return Ok(out);
+        }
+    }
+    Err(format!("no free short name for `{name}`"))
+}
+
+fn lfn_count(node: &Node) -> usize {
+    if exact_short(&node.name).is_some() {
+        0
+    } else {
+        node.name.encode_utf16().count().div_ceil(LFN_CHARS)
+    }
+}
+
+fn lfn_checksum(short: &[u8; 11]) -> u8 {
+    short.iter().fold(0u8, |sum, &b| {
+        (sum >> 1).wrapping_add(sum << 7).wrapping_add(b)
+    })
+}
+
+fn dir_bytes(
+    nodes: &[Node],
+    me: usize,
+    children: &[usize],
+    label: &str,
+    cluster_bytes: u64,
+) -> Vec<u8> {
+    let node = &nodes[me];
+    let mut out = Vec::new();
+
+    if me == 0 {
+        let mut l = [b' '; 11];
+        for (d, s) in l.iter_mut().zip(label.bytes()) {
+            *d = s.to_ascii_uppercase();
+        }
+        out.extend_from_slice(&short_entry(&l, ATTR_VOLUME_ID, 0, 0));
+    } else {
+        // `..` pointing at the root is encoded as cluster 0
+        let parent = if node.parent == 0 {
+            0
+        } else {
+            nodes[node.parent].cluster
+        };
+        out.extend_from_slice(&short_entry(
+            b".          ",
+            ATTR_DIRECTORY,
+            node.cluster,
+            0,
+        ));
+        out.extend_from_slice(&short_entry(b"..         ", ATTR_DIRECTORY, parent, 0));
+    }
+
+    for &c in children {
+        let child = &nodes[c];
+        let (attr, size) = match &child.kind {
+            Kind::Dir(_) => (ATTR_DIRECTORY, 0),
+            Kind::File(data) => (ATTR_ARCHIVE, data.len() as u32),
+        };
+        out.extend_from_slice(&lfn_entries(child));
+        out.extend_from_slice(&short_entry(&child.short, attr, child.cluster, size));
+    }
+
+    // Zero padding doubles as the end-of-directory marker
+    out.resize((node.clusters as u64 * cluster_bytes) as usize, 0);
+    out
+}
+
+fn lfn_entries(node: &Node) -> Vec<u8> {
+    let count = lfn_count(node);
+    if count == 0 {
+        return Vec::new();
+    }
+    let mut units: Vec<u16> = node.name.encode_utf16().collect();
+    if !units.len().is_multiple_of(LFN_CHARS) {
+        units.push(0);
+        units.resize(count * LFN_CHARS, 0xFFFF);
+    }
+    let sum = lfn_checksum(&node.short);
+
+    let mut out = Vec::with_capacity(count * DIR_ENTRY);
+    for seq in (1..=count).rev() {
+        let chunk = &units[(seq - 1) * LFN_CHARS..seq * LFN_CHARS];
+        let mut e = [0u8; DIR_ENTRY];
+        e[0] = seq as u8 | if seq == count { 0x40 } else { 0 };
+        e[11] = ATTR_LFN;
+        e[13] = sum;
+        let slots = (1..11)
+            .step_by(2)
+            .chain((14..26).step_by(2))
+            .chain((28..32).step_by(2));
+        for (off, u) in slots.zip(chunk) {
+            e[off..off + 2].copy_from_slice(&u.to_le_bytes());
+        }
+        out.extend_from_slice(&e);
+    }
+    out
+}
+
+fn short_entry(name: &[u8; 11], attr: u8, cluster: u32, size: u32) -> [u8; DIR_ENTRY] {
+    let mut e = [0u8; DIR_ENTRY];
+    e[0..11].copy_from_slice(name);
+    e[11] = attr;
+    e[14..16].copy_from_slice(&DOS_TIME.to_le_bytes());
+    e[16..18].copy_from_slice(&DOS_DATE.to_le_bytes());
+    e[18..20].copy_from_slice(&DOS_DATE.to_le_bytes());
+    e[20..22].copy_from_slice(&((cluster >> 16) as u16).to_le_bytes());
+    e[22..24].copy_from_slice(&DOS_TIME.to_le_bytes());
+    e[24..26].copy_from_slice(&DOS_DATE.to_le_bytes());
+    e[26..28].copy_from_slice(&(cluster as u16).to_le_bytes());
+    e[28..32].copy_from_slice(&size.to_le_bytes());
+    e
+}
+
+fn boot_sector(geo: &Geometry, hidden: u64, label: &str, volume_id: u32) -> Vec<u8> {
+    let mut b = vec![0u8; SECTOR as usize];
+    b[0..3].copy_from_slice(&[0xEB, 0x58, 0x90]);
+    b[3..11].copy_from_slice(b"NONOS   ");
+    b[11..13].copy_from_slice(&(SECTOR as u16).to_le_bytes());
+    b[13] = geo.spc as u8;
+    b[14..16].copy_from_slice(&(RESERVED_SECTORS as u16).to_le_bytes());
+    b[16] = NUM_FATS as u8;
+    b[21] = 0xF8;
+    b[24..26].copy_from_slice(&32u16.to_le_bytes());
+    b[26..28].copy_from_slice(&64u16.to_le_bytes());
+    b[28..32].copy_from_slice(&(hidden as u32).to_le_bytes());
+    b[32..36].copy_from_slice(&(geo.sectors as u32).to_le_bytes());
+    b[36..40].copy_from_slice(&(geo.fat_sectors as u32).to_le_bytes());
+    b[44..48].copy_from_slice(&2u32.to_le_bytes());
+    b[48..50].copy_from_slice(&(FSINFO_SECTOR as u16).to_le_bytes());
+    b[50..52].copy_from_slice(&(BACKUP_BOOT_SECTOR as u16).to_le_bytes());
+    b[64] = 0x80;
+    b[66] = 0x29;
+    b[67..71].copy_from_slice(&volume_id.to_le_bytes());
+    let mut l = [b' '; 11];
+    for (d, s) in l.iter_mut().zip(label.bytes()) {
+        *d = s.to_ascii_uppercase();
+    }
+    b[71..82].copy_from_slice(&l);
+    b[82..90].copy_from_slice(b"FAT32   ");
+    b[510] = 0x55;
+    b[511] = 0xAA;
+    b
+}
+
+fn fsinfo(free: u64, next_free: u32) -> Vec<u8> {
+    let mut s = vec![0u8; SECTOR as usize];
+    s[0..4].copy_from_slice(&0x4161_5252u32.to_le_bytes());
+    s[484..488].copy_from_slice(&0x6141_7272u32.to_le_bytes());
+    s[488..492].copy_from_slice(&(free as u32).to_le_bytes());
+    s[492..496].copy_from_slice(&next_free.to_le_bytes());
+    s[508..512].copy_from_slice(&0xAA55_0000u32.to_le_bytes());
+    s
+}
@@ -0,0 +1,109 @@
+//! GUID Partition Table writer (protective MBR, primary + backup headers).
+
+use super::{crc32, SECTOR};
+
+/// Entries per table and bytes per entry (UEFI defaults)
+const ENTRIES: usize = 128;
+const ENTRY_SIZE: usize = 128;
+const TABLE_SECTORS: u64 = (ENTRIES * ENTRY_SIZE) as u64 / SECTOR;
+
+/// EFI System Partition type GUID C12A7328-F81F-11D2-BA4B-00A0C93EC93B
+pub(crate) const ESP_TYPE: [u8; 16] = guid(
+    0xC12A7328,
+    0xF81F,
+    0x11D2,
+    [0xBA, 0x4B, 0x00, 0xA0, 0xC9, 0x3E, 0xC9, 0x3B],
+);
+
+/// GUID in on-disk (mixed-endian) byte order
+pub(crate) const fn guid(a: u32, b: u16, c: u16, d: [u8; 8]) -> [u8; 16] {
+    let a = a.to_le_bytes();
+    let b = b.to_le_bytes();
+    let c = c.to_le_bytes();
+    [
+        a[0], a[1], a[2], a[3], b[0], b[1], c[0], c[1], d[0], d[1], d[2], d[3], d[4], d[5], d[6],
+        d[7],
+    ]
+}
+
+pub(crate) struct Partition {
+    pub type_guid: [u8; 16],
+    pub unique_guid: [u8; 16],
+    pub first_lba: u64,
+    /// Inclusive
+    pub last_lba: u64,
+    pub name: &'static str,
+}
+
+/// First LBA available to partitions
+pub(crate) const fn first_usable() -> u64 {
+    2 + TABLE_SECTORS
+}
+
+/// Last LBA available to partitions on a disk of `disk_sectors`
+pub(crate) const fn last_usable(disk_sectors: u64) -> u64 {
+    disk_sectors - 2 - TABLE_SECTORS
+}
+
+/// Returns (offset, bytes) regions to write: MBR, primary header+table, backup table+header.
+pub(crate) fn build(
+    disk_sectors: u64,
+    disk_guid: [u8; 16],
+    parts: &[Partition],
+) -> Vec<(u64, Vec<u8>)> {
+    let mut table = vec![0u8; ENTRIES * ENTRY_SIZE];
+    for (i, p) in parts.iter().enumerate() {
+        let e = &mut table[i * ENTRY_SIZE..(i + 1) * ENTRY_SIZE];
+        e[0..16].copy_from_slice(&p.type_guid);
+        e[16..32].copy_from_slice(&p.unique_guid);
+        e[32..40].copy_from_slice(&p.first_lba.to_le_bytes());
+        e[40..48].copy_from_slice(&p.last_lba.to_le_bytes());
+        for (j, u) in p.name.encode_utf16().take(36).enumerate() {
+            e[56 + j * 2..58 + j * 2].copy_from_slice(&u.to_le_bytes());
+        }
+    }
+    let table_crc = crc32(&table);
+
+    let last = disk_sectors - 1;
+    let backup_table = last - TABLE_SECTORS;
+    let header = |my: u64, alt: u64, table_lba: u64| {
+        let mut h = vec![0u8; SECTOR as usize];
+        h[0..8].copy_from_slice(b"EFI PART");
+        h[8..12].copy_from_slice(&0x0001_0000u32.to_le_bytes());
+        h[12..16].copy_from_slice(&92u32.to_le_bytes());
+        h[24..32].copy_from_slice(&my.to_le_bytes());
+        h[32..40].copy_from_slice(&alt.to_le_bytes());
+        h[40..48].copy_from_slice(&first_usable().to_le_bytes());
+        h[48..56].copy_from_slice(&last_usable(disk_sectors).to_le_bytes());
+        h[56..72].copy_from_slice(&disk_guid);
+        h[72..80].copy_from_slice(&table_lba.to_le_bytes());
+        h[80..84].copy_from_slice(&(ENTRIES as u32).to_le_bytes());
+        h[84..88].copy_from_slice(&(ENTRY_SIZE as u32).to_le_bytes());
+        h[88..92].copy_from_slice(&table_crc.to_le_bytes());
+        let crc = crc32(&h[..92]);
+        h[16..20].copy_from_slice(&crc.to_le_bytes());
+        h
+    };
+
+    vec![
+        (0, protective_mbr(disk_sectors)),
+        (SECTOR, header(1, last, 2)),
+        (2 * SECTOR, table.clone()),
+        (backup_table * SECTOR, table),
+        (last * SECTOR, header(last, 1, backup_table)),
+    ]
+}
+
+fn protective_mbr(disk_sectors: u64) -> Vec<u8> {
+    let mut mbr = vec![0u8; SECTOR as usize];
+    let size = (disk_sectors - 1).min(u32::MAX as u64) as u32;
+    let e = &mut mbr[446..462];
+    e[1..4].copy_from_slice(&[0x00, 0x02, 0x00]); // CHS of LBA 1
+    e[4] = 0xEE;
+    e[5..8].copy_from_slice(&[0xFF, 0xFF, 0xFF]);
+    e[8..12].copy_from_slice(&1u32.to_le_bytes());
+    e[12..16].copy_from_slice(&size.to_le_bytes());
+    mbr[510] = 0x55;
+    mbr[511] = 0xAA;
+    mbr
+}
@@ -0,0 +1,138 @@
+//! Declarative disk layout files.
+//!
+//! One whitespace-separated directive per line (paths cannot contain spaces); lines starting
+//! with `#` are comments. `${NAME}` expands from `--var NAME=VALUE` first, then the
+//! environment. Relative host paths resolve against the workspace root. `${BOOT_FILE}` is
+//! predefined as the removable-media path for `--arch` (`EFI/BOOT/BOOTX64.EFI` or
+//! `EFI/BOOT/BOOTAA64.EFI`), so one layout serves both architectures.
+//!
+//! ```text
+//! disk-size 128M                      total image size (K/M/G suffixes)
+//! esp-size  100M                      ESP partition size (default: all usable space)
+//! label     NONOS                     FAT volume label (max 11 chars)
+//! loader    ${BOOT_FILE}              where the built loader goes
+//! file      EFI/nonos/boot.cfg  ${CONFIG}
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::Arch;
+
+pub(crate) struct Layout {
+    pub disk_size: u64,
+    pub esp_size: Option<u64>,
+    pub label: String,
+    /// ESP path for the loader binary, if the layout wants one
+    pub loader: Option<String>,
+    /// (ESP path, host path)
+    pub files: Vec<(String, PathBuf)>,
+}
+
+impl Layout {
+    pub(crate) fn load(
+        path: &Path,
+        arch: Arch,
+        vars: &BTreeMap<String, String>,
+    ) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("read {}: {e}", path.display()))?;
+        let mut vars = vars.clone();
+        vars.entry("BOOT_FILE".to_string())
+            .or_insert_with(|| arch.boot_file().to_string());
+        Self::parse(&text, &vars).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    fn parse(text: &str, vars: &BTreeMap<String, String>) -> Result<Self, String> {
+        let mut layout = Layout {
+            disk_size: 0,
+            esp_size: None,
+            label: "NONOS".to_string(),
+            loader: None,
+            files: Vec::new(),
+        };
+
+        for (n, raw) in text.lines().enumerate() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let at = |e: String| format!("line {}: {e}", n + 1);
+            let line = expand(line, vars).map_err(at)?;
+            let mut words = line.split_whitespace();
+            let key = words.next().unwrap_or_default();
+            let args: Vec<&str> = words.collect();
+
+            match (key, args.as_slice()) {
+                ("disk-size", [v]) => layout.disk_size = parse_size(v).map_err(at)?,
+                ("esp-size", [v]) => layout.esp_size = Some(parse_size(v).map_err(at)?),
+                ("label", [v]) => layout.label = v.to_string(),
+                ("loader", [dest]) => layout.loader = Some(esp_path(dest).map_err(at)?),
+                ("file", [dest, src]) => {
+                    let dest = esp_path(dest).map_err(at)?;
+                    layout.files.push((dest, crate::workspace_root().join(src)));
+                }
+                ("disk-size" | "esp-size" | "label" | "loader" | "file", _) => {
+                    return Err(at(format!("wrong number of arguments to `{key}`")));
+                }
+                _ => return Err(at(format!("unknown directive `{key}`"))),
+            }
+        }
+
+        if layout.disk_size == 0 {
+            return Err("missing `disk-size`".into());
+        }
+        if layout.label.is_empty() || layout.label.len() > 11 || !layout.label.is_ascii() {
+            return Err(format!(
+                "label `{}` must be 1-11 ASCII characters",
+                layout.label
+            ));
+        }
+        Ok(layout)
+    }
+}
+
+/// `${NAME}` substitution; unknown names are an error rather than silently empty.
+fn expand(line: &str, vars: &BTreeMap<String, String>) -> Result<String, String> {
+    let mut out = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start + 2..];
+        let end = tail.find('}').ok_or("unterminated `${`")?;
+        let name = &tail[..end];
+        let value = vars
+            .get(name)
+            .cloned()
+            .or_else(|| env::var(name).ok())
+            .ok_or_else(|| format!("`${{{name}}}` is not set (use --var {name}=...)"))?;
+        out.push_str(&value);
+        rest = &tail[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn esp_path(p: &str) -> Result<String, String> {
+    let p = p.trim_matches('/');
+    if p.is_empty() || p.split('/').any(|c| c.is_empty() || c == "." || c == "..") {
+        return Err(format!("bad ESP path `{p}`"));
+    }
+    Ok(p.to_string())
+}
+
+/// `4096`, `64K`, `128M`, `2G` (binary multiples)
+pub(crate) fn parse_size(s: &str) -> Result<u64, String> {
+    let (num, mul) = match s.char_indices().last() {
+        Some((i, 'K' | 'k')) => (&s[..i], 1u64 << 10),
+        Some((i, 'M' | 'm')) => (&s[..i], 1 << 20),
+        Some((i, 'G' | 'g')) => (&s[..i], 1 << 30),
+        _ => (s, 1),
+    };
+    num.parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(mul))
+        .ok_or_else(|| format!("bad size `{s}`"))
+}
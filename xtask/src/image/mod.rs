@@ -0,0 +1,188 @@
+//! `cargo xtask image` — bootable GPT disk image (FAT32 ESP) from a declarative layout.
+
+mod fat;
+mod gpt;
+mod layout;
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::Flags;
+pub(crate) use layout::{parse_size, Layout};
+
+pub(crate) const SECTOR: u64 = 512;
+
+/// ESP starts at 1 MiB, the alignment every partitioning tool defaults to
+const ESP_FIRST_LBA: u64 = 2048;
+
+const DEFAULT_LAYOUT: &str = "xtask/layouts/qemu.layout";
+
+pub(crate) fn run(raw: &[String]) -> Result<(), String> {
+    let mut f = Flags::parse(raw, &["release"])?;
+    let release = f.switch("release");
+    let features = f.value("features");
+    let arch = crate::Arch::from_flags(&mut f)?;
+    let prebuilt = f.value("efi").map(PathBuf::from);
+    let layout_path = f
+        .value("layout")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| crate::workspace_root().join(DEFAULT_LAYOUT));
+    let out = f
+        .value("out")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| crate::xtask_dir().join("nonos.img"));
+    let vars = parse_vars(f.values("var"))?;
+    f.finish()?;
+
+    let layout = Layout::load(&layout_path, arch, &vars)?;
+    let loader = match (&layout.loader, prebuilt) {
+        (None, _) => None,
+        (Some(_), Some(p)) => Some(p),
+        (Some(_), None) => Some(crate::build_efi(arch, release, features.as_deref())?),
+    };
+
+    write_image(&layout, loader.as_deref(), &out)?;
+    println!(
+        "[+] wrote {} ({} MiB)",
+        out.display(),
+        layout.disk_size >> 20
+    );
+    Ok(())
+}
+
+pub(crate) fn parse_vars(raw: Vec<String>) -> Result<BTreeMap<String, String>, String> {
+    raw.into_iter()
+        .map(|kv| match kv.split_once('=') {
+            Some((k, v)) if !k.is_empty() => Ok((k.to_string(), v.to_string())),
+            _ => Err(format!("--var expects NAME=VALUE, got `{kv}`")),
+        })
+        .collect()
+}
+
+/// Write the GPT disk image described by `layout` to `out`.
+pub(crate) fn write_image(
+    layout: &Layout,
+    loader: Option<&Path>,
+    out: &Path,
+) -> Result<(), String> {
+    let mut files = Vec::new();
+    if let (Some(dest), Some(src)) = (&layout.loader, loader) {
+        files.push((dest.clone(), read(src)?));
+    }
+    for (dest, src) in &layout.files {
+        files.push((dest.clone(), read(src)?));
+    }
+
+    let disk_sectors = layout.disk_size / SECTOR;
+    if disk_sectors <= ESP_FIRST_LBA + gpt::first_usable() {
+        return Err("disk-size too small".into());
+    }
+    let max_esp = gpt::last_usable(disk_sectors) - ESP_FIRST_LBA + 1;
+    let esp_sectors = match layout.esp_size {
+        Some(sz) if sz / SECTOR > max_esp => {
+            return Err(format!(
+                "esp-size exceeds the usable disk space ({} MiB)",
+                (max_esp * SECTOR) >> 20
+            ));
+        }
+        Some(sz) => sz / SECTOR,
+        None => max_esp,
+    };
+
+    // Identifiers derive from the inputs so identical inputs give byte-identical images
+    let mut seed = Fnv::new();
+    seed.write(layout.label.as_bytes());
+    seed.write(&layout.disk_size.to_le_bytes());
+    for (dest, data) in &files {
+        seed.write(dest.as_bytes());
+        seed.write(data);
+    }
+    let disk_guid = seed.guid(1);
+    let esp_guid = seed.guid(2);
+    let volume_id = seed.finish(3) as u32;
+
+    let mut regions = gpt::build(
+        disk_sectors,
+        disk_guid,
+        &[gpt::Partition {
+            type_guid: gpt::ESP_TYPE,
+            unique_guid: esp_guid,
+            first_lba: ESP_FIRST_LBA,
+            last_lba: ESP_FIRST_LBA + esp_sectors - 1,
+            name: "EFI System",
+        }],
+    );
+    let esp_base = ESP_FIRST_LBA * SECTOR;
+    for (off, bytes) in fat::build(esp_sectors, ESP_FIRST_LBA, &layout.label, volume_id, &files)? {
+        regions.push((esp_base + off, bytes));
+    }
+
+    if let Some(dir) = out.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("create {}: {e}", dir.display()))?;
+    }
+    let mut img = fs::File::create(out).map_err(|e| format!("create {}: {e}", out.display()))?;
+    // Sparse where the filesystem allows; unwritten space reads as zero
+    img.set_len(disk_sectors * SECTOR)
+        .map_err(|e| format!("size {}: {e}", out.display()))?;
+    for (off, bytes) in regions {
+        img.seek(SeekFrom::Start(off))
+            .and_then(|_| img.write_all(&bytes))
+            .map_err(|e| format!("write {}: {e}", out.display()))?;
+    }
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|e| format!("read {}: {e}", path.display()))
+}
+
+/// IEEE CRC-32 as required by the GPT headers
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// FNV-1a accumulator for deriving stable identifiers (not a security primitive)
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, data: &[u8]) {
+        for &b in data {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self, salt: u64) -> u64 {
+        let mut h = Fnv(self.0);
+        h.write(&salt.to_le_bytes());
+        h.0
+    }
+
+    /// RFC 9562 version-8 (custom) GUID
+    fn guid(&self, salt: u64) -> [u8; 16] {
+        let mut g = [0u8; 16];
+        g[..8].copy_from_slice(&self.finish(salt).to_le_bytes());
+        g[8..].copy_from_slice(&self.finish(!salt).to_le_bytes());
+        g[7] = (g[7] & 0x0F) | 0x80;
+        g[8] = (g[8] & 0x3F) | 0x80;
+        g
+    }
+}
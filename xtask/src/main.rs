@@ -0,0 +1,326 @@
+//! NONOS xtask — developer automation for the bootloader (host-side)
+//!
+//! Author: eK (team@nonos.systems) — https://nonos.systems
+//! Purpose: one entry point (`cargo xtask <cmd>`) for the things we used to do with shell snippets.
+
+mod bench;
+mod features;
+mod image;
+mod qemu;
+mod release;
+mod scenario;
+mod secure_boot;
+mod serial;
+mod sha256;
+mod size;
+mod test_boot;
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Loader architectures the harness can build and boot
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Arch {
+    X86_64,
+    Aarch64,
+}
+
+impl Arch {
+    pub(crate) fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "x86_64" | "x64" => Ok(Arch::X86_64),
+            "aarch64" | "arm64" => Ok(Arch::Aarch64),
+            _ => Err(format!("--arch expects x86_64|aarch64, got `{s}`")),
+        }
+    }
+
+    /// `--arch` value, defaulting to x86_64
+    pub(crate) fn from_flags(f: &mut Flags) -> Result<Self, String> {
+        f.value("arch")
+            .map(|a| Arch::parse(&a))
+            .transpose()
+            .map(|a| a.unwrap_or(Arch::X86_64))
+    }
+
+    /// UEFI target triple the loader is built for (keep in sync with the Makefile)
+    pub(crate) fn uefi_target(self) -> &'static str {
+        match self {
+            Arch::X86_64 => "x86_64-unknown-uefi",
+            Arch::Aarch64 => "aarch64-unknown-uefi",
+        }
+    }
+
+    /// Removable-media boot path firmware falls back to
+    pub(crate) fn boot_file(self) -> &'static str {
+        match self {
+            Arch::X86_64 => "EFI/BOOT/BOOTX64.EFI",
+            Arch::Aarch64 => "EFI/BOOT/BOOTAA64.EFI",
+        }
+    }
+
+    pub(crate) fn default_qemu(self) -> &'static str {
+        match self {
+            Arch::X86_64 => "qemu-system-x86_64",
+            Arch::Aarch64 => "qemu-system-aarch64",
+        }
+    }
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("[x] {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut args = env::args().skip(1);
+    let cmd = args.next();
+    let rest: Vec<String> = args.collect();
+
+    match cmd.as_deref() {
+        Some("test-boot") => test_boot::run(&rest),
+        Some("image") => image::run(&rest),
+        Some("bench-boot") => bench::run(&rest),
+        Some("release") => release::run(&rest),
+        Some("size") => size::run(&rest),
+        Some("features") => features::run(&rest),
+        Some("serial") => serial::run(&rest),
+        Some("help" | "-h" | "--help") | None => {
+            print_help();
+            Ok(())
+        }
+        Some(other) => Err(format!(
+            "unknown command `{other}` (see `cargo xtask help`)"
+        )),
+    }
+}
+
+fn print_help() {
+    println!("NONOS xtask");
+    println!();
+    println!("USAGE: cargo xtask <command> [options]");
+    println!();
+    println!("Commands:");
+    println!("  test-boot   Build the EFI binary, assemble an ESP and boot it under QEMU+OVMF,");
+    println!("              asserting on serial output (see xtask/scenarios/)");
+    println!(
+        "  image       Write a bootable GPT disk image from a layout file (see xtask/layouts/)"
+    );
+    println!(
+        "  bench-boot  Boot repeatedly across QEMU configurations and report per-phase timings"
+    );
+    println!("  serial      Render a serial capture and export its @nonos records");
+    println!("  size        Build the feature matrix and enforce binary size budgets");
+    println!("  features    Check feature-combination rules (build/feature_rules.rs)");
+    println!("  release     Reproducible release artifacts + SHA256SUMS + provenance (--verify)");
+    println!();
+    println!("test-boot options:");
+    println!("  --arch <ARCH>           x86_64 (default) or aarch64 (virt machine + AAVMF)");
+    println!("  --release               Build the loader in release mode");
+    println!("  --features <LIST>       Cargo features for the loader build");
+    println!("  --efi <PATH>            Use a prebuilt loader instead of building one");
+    println!("  --kernel <PATH>         Test kernel copied into the ESP");
+    println!("  --capsule <PATH>        Capsule copied into the ESP");
+    println!("  --config <PATH>         Boot config copied into the ESP");
+    println!(
+        "  --scenario <PATH>       Scenario file (repeatable; default: scenarios/boot-ok.scenario)"
+    );
+    println!("  --expect-markers        Enforce expect/until lines on @nonos records (needs a");
+    println!("                          loader that emits them). Without it they are dropped,");
+    println!("                          and a scenario left with nothing to reach is skipped");
+    println!("                          and fails the run");
+    println!("  --ovmf <PATH>           OVMF_CODE.fd (or combined OVMF.fd); env OVMF_CODE,");
+    println!("                          or AAVMF_CODE for aarch64");
+    println!("  --ovmf-vars <PATH>      OVMF_VARS.fd template; env OVMF_VARS / AAVMF_VARS");
+    println!("  --qemu <BIN>            QEMU binary (default: qemu-system-<arch>); env QEMU");
+    println!("  --layout <PATH>         Boot a disk image built from this layout instead of an");
+    println!("                          ESP directory (--kernel/--capsule/--config are ignored)");
+    println!("  --var <NAME=VALUE>      Layout variable (repeatable)");
+    println!("  --verbose               Echo serial output while booting");
+    println!("  --secure-boot           Enroll test keys, sign the loader, boot with Secure Boot");
+    println!("                          enforced, then check an unsigned loader is refused");
+    println!("  --sb-keys <DIR>         Test PK/KEK/db keys (default: target/xtask/sb-keys)");
+    println!("  --shim <PATH>           Chain through this shim (signed with the test db key)");
+    println!();
+    println!("image options:");
+    println!("  --layout <PATH>         Layout file (default: xtask/layouts/qemu.layout)");
+    println!("  --out <PATH>            Output image (default: target/xtask/nonos.img)");
+    println!("  --var <NAME=VALUE>      Value for ${{NAME}} in the layout (repeatable)");
+    println!("  --efi <PATH>            Use a prebuilt loader instead of building one");
+    println!("  --release, --features   As for test-boot");
+    println!("  --arch <ARCH>           Loader target; sets ${{BOOT_FILE}} to match");
+    println!();
+    println!("bench-boot options:");
+    println!("  --runs <N>              Boots per configuration (default: 3)");
+    println!("  --cpus <LIST>           vCPU counts to try, comma-separated (default: 1,4)");
+    println!("  --memory <LIST>         Guest memory sizes in MiB (default: 512)");
+    println!("  --secure-boot           Add `sbon` variants: signed loader, enrolled test keys");
+    println!("  --sb-ovmf <PATH>        Secure Boot enabled OVMF_CODE for the `sbon` variants");
+    println!("  --sb-vars <PATH>        OVMF_VARS template the test keys are enrolled into");
+    println!("  --sb-keys, --shim       As for test-boot");
+    println!("  --scenario <PATH>       Scenario supplying `until`/timeout (default: boot-ok)");
+    println!("  --out <DIR>             Report directory (default: target/xtask/bench)");
+    println!("  --baseline <TSV>        Compare medians against an earlier report.tsv");
+    println!("  --threshold <PCT>       Allowed slowdown per phase before failing (default: 10)");
+    println!("  loader/ESP/QEMU options as for test-boot");
+    println!();
+    println!("size options:");
+    println!("  --budget <PATH>         Budget file (default: xtask/budgets/size.budget)");
+    println!("  --only <NAME>           Check only this configuration (repeatable)");
+    println!("  --symbols               Per-crate breakdown via cargo-bloat");
+    println!("  --arch <ARCH>           Loader architecture (default: x86_64)");
+    println!();
+    println!("features options:");
+    println!("  --features <LIST>       Check only this combination");
+    println!("  --build                 Also `cargo check` every single feature and pair");
+    println!("  --arch <ARCH>           Target for --build (default: x86_64)");
+    println!("  --release               Apply the release-only rules to the matrix and --build");
+    println!();
+    println!("release options:");
+    println!(
+        "  --arch <ARCH>           Loader architecture (repeatable; default: x86_64 and aarch64)"
+    );
+    println!("  --features <LIST>       Cargo features for the loader build");
+    println!("  --out <DIR>             Artifact directory (default: target/xtask/release/<name>)");
+    println!(
+        "  --verify                Rebuild from scratch and compare with an existing manifest"
+    );
+    println!("  --manifest <PATH>       SHA256SUMS to verify against (default: the one in --out)");
+    println!("  --allow-dirty           Build even with uncommitted changes (never for releases)");
+    println!();
+    println!("serial options:");
+    println!("  --input <PATH|->        Capture file or serial device (default: stdin)");
+    println!("  --json <PATH>           Write parsed @nonos records as a JSON array");
+    println!("  --tee <PATH>            Also save the raw capture");
+    println!("  --fail-on <LEVEL>       Exit non-zero on records at error|warn|info or worse");
+    println!("  --only-records          Hide unstructured (firmware) lines");
+    println!("  --no-color              Plain output even on a terminal");
+}
+
+/* ---------------- shared helpers ---------------- */
+
+/// Workspace root (xtask lives one level below it)
+pub(crate) fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// Scratch directory for everything xtask produces
+pub(crate) fn xtask_dir() -> PathBuf {
+    workspace_root().join("target").join("xtask")
+}
+
+pub(crate) fn cargo() -> Command {
+    Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+}
+
+pub(crate) fn run_cmd(cmd: &mut Command) -> Result<(), String> {
+    let status = cmd
+        .status()
+        .map_err(|e| format!("spawn {}: {e}", cmd.get_program().to_string_lossy()))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} failed ({status})",
+            cmd.get_program().to_string_lossy()
+        ))
+    }
+}
+
+/// Build the loader for the UEFI target and return the path of the produced `.efi`.
+pub(crate) fn build_efi(
+    arch: Arch,
+    release: bool,
+    features: Option<&str>,
+) -> Result<PathBuf, String> {
+    let root = workspace_root();
+    let target = arch.uefi_target();
+    let mut cmd = cargo();
+    cmd.current_dir(&root)
+        .args(["build", "--package", "nonos_boot", "--target", target]);
+    if release {
+        cmd.arg("--release");
+    }
+    if let Some(f) = features {
+        cmd.args(["--features", f]);
+    }
+    println!(
+        "[*] building loader ({target}, {})",
+        if release { "release" } else { "debug" }
+    );
+    run_cmd(&mut cmd)?;
+
+    let profile = if release { "release" } else { "debug" };
+    let efi = root
+        .join("target")
+        .join(target)
+        .join(profile)
+        .join("nonos_boot.efi");
+    if !efi.is_file() {
+        return Err(format!("build succeeded but {} is missing", efi.display()));
+    }
+    Ok(efi)
+}
+
+/// Minimal `--name value` / `--switch` parser; keeps xtask free of clap.
+pub(crate) struct Flags {
+    items: Vec<(String, Option<String>)>,
+}
+
+impl Flags {
+    /// `switches` take no value; every other `--name` consumes the next argument.
+    pub(crate) fn parse(raw: &[String], switches: &[&str]) -> Result<Self, String> {
+        let mut items = Vec::new();
+        let mut it = raw.iter();
+        while let Some(arg) = it.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("unexpected argument `{arg}`"))?;
+            if switches.contains(&name) {
+                items.push((name.to_string(), None));
+            } else {
+                let value = it.next().ok_or_else(|| format!("--{name} needs a value"))?;
+                items.push((name.to_string(), Some(value.clone())));
+            }
+        }
+        Ok(Self { items })
+    }
+
+    pub(crate) fn switch(&mut self, name: &str) -> bool {
+        let before = self.items.len();
+        self.items.retain(|(n, v)| !(n == name && v.is_none()));
+        self.items.len() != before
+    }
+
+    /// Last occurrence wins.
+    pub(crate) fn value(&mut self, name: &str) -> Option<String> {
+        self.values(name).pop()
+    }
+
+    pub(crate) fn values(&mut self, name: &str) -> Vec<String> {
+        let mut out = Vec::new();
+        self.items.retain(|(n, v)| match v {
+            Some(v) if n == name => {
+                out.push(v.clone());
+                false
+            }
+            _ => true,
+        });
+        out
+    }
+
+    /// Reject anything the command did not consume.
+    pub(crate) fn finish(self) -> Result<(), String> {
+        match self.items.first() {
+            Some((name, _)) => Err(format!("unknown option --{name}")),
+            None => Ok(()),
+        }
+    }
+}
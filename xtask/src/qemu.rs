@@ -0,0 +1,287 @@
+//! QEMU + OVMF launcher with line-oriented serial capture.
+
+use std::{
+    env, fs,
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::Arch;
+
+/// Well-known OVMF install locations, probed when neither --ovmf nor OVMF_CODE is given
+const OVMF_SEARCH: &[&str] = &[
+    "/usr/share/OVMF/OVMF_CODE.fd",
+    "/usr/share/OVMF/OVMF_CODE_4M.fd",
+    "/usr/share/edk2/ovmf/OVMF_CODE.fd",
+    "/usr/share/edk2/x64/OVMF_CODE.fd",
+    "/usr/share/edk2-ovmf/x64/OVMF_CODE.fd",
+    "/usr/share/qemu/OVMF.fd",
+];
+
+/// Secure Boot capable (SMM) OVMF builds, probed for the Secure Boot profile
+const OVMF_SB_SEARCH: &[&str] = &[
+    "/usr/share/OVMF/OVMF_CODE.secboot.fd",
+    "/usr/share/OVMF/OVMF_CODE_4M.secboot.fd",
+    "/usr/share/edk2/ovmf/OVMF_CODE.secboot.fd",
+    "/usr/share/edk2/x64/OVMF_CODE.secboot.4m.fd",
+    "/usr/share/edk2-ovmf/x64/OVMF_CODE.secboot.fd",
+];
+
+/// Same for AAVMF (ArmVirtQemu); probed when neither --ovmf nor AAVMF_CODE is given
+const AAVMF_SEARCH: &[&str] = &[
+    "/usr/share/AAVMF/AAVMF_CODE.fd",
+    "/usr/share/edk2/aarch64/QEMU_EFI-pflash.raw",
+    "/usr/share/edk2/aarch64/QEMU_EFI.fd",
+    "/usr/share/qemu-efi-aarch64/QEMU_EFI.fd",
+    "/usr/share/qemu/edk2-aarch64-code.fd",
+];
+
+/// UEFI firmware image(s) handed to QEMU
+#[derive(Clone)]
+pub(crate) enum Firmware {
+    /// Split CODE (read-only) + VARS (per-run writable copy) pflash images
+    Split { code: PathBuf, vars: PathBuf },
+    /// Single combined image passed as `-bios`
+    Combined(PathBuf),
+}
+
+impl Firmware {
+    /// Resolve firmware from explicit paths, the OVMF_*/AAVMF_* env vars, or known locations.
+    /// `secure` prefers Secure Boot capable builds (AAVMF builds all support it).
+    pub(crate) fn locate(
+        arch: Arch,
+        secure: bool,
+        code: Option<String>,
+        vars: Option<String>,
+    ) -> Result<Self, String> {
+        let (name, search) = match (arch, secure) {
+            (Arch::X86_64, false) => ("OVMF", OVMF_SEARCH),
+            (Arch::X86_64, true) => ("OVMF", OVMF_SB_SEARCH),
+            (Arch::Aarch64, _) => ("AAVMF", AAVMF_SEARCH),
+        };
+        let code = code
+            .or_else(|| env::var(format!("{name}_CODE")).ok())
+            .map(PathBuf::from)
+            .or_else(|| search.iter().map(PathBuf::from).find(|p| p.is_file()))
+            .ok_or_else(|| {
+                format!("{name} not found; pass --ovmf <{name}_CODE.fd> or set {name}_CODE")
+            })?;
+        if !code.is_file() {
+            return Err(format!("firmware {} does not exist", code.display()));
+        }
+
+        let vars = vars
+            .or_else(|| env::var(format!("{name}_VARS")).ok())
+            .map(PathBuf::from)
+            .or_else(|| sibling_vars(&code));
+        match vars {
+            Some(vars) if vars.is_file() => Ok(Firmware::Split { code, vars }),
+            Some(vars) => Err(format!("firmware vars {} does not exist", vars.display())),
+            None => Ok(Firmware::Combined(code)),
+        }
+    }
+}
+
+/// OVMF_CODE*.fd usually ships next to a matching OVMF_VARS*.fd
+/// (the Secure Boot CODE build shares the plain VARS template on some distros)
+fn sibling_vars(code: &Path) -> Option<PathBuf> {
+    let name = code.file_name()?.to_str()?;
+    if !name.contains("CODE") {
+        return None;
+    }
+    let vars = name.replace("CODE", "VARS");
+    [vars.clone(), vars.replace(".secboot", "")]
+        .into_iter()
+        .map(|n| code.with_file_name(n))
+        .find(|p| p.is_file())
+}
+
+/// Boot medium presented to the guest
+#[derive(Clone)]
+pub(crate) enum Disk {
+    /// Directory exposed as a FAT ESP (QEMU vvfat)
+    EspDir(PathBuf),
+    /// Raw disk image (e.g. from `cargo xtask image`)
+    Image(PathBuf),
+}
+
+/// One emulated machine configuration
+pub(crate) struct Machine {
+    pub arch: Arch,
+    pub qemu: String,
+    pub firmware: Firmware,
+    pub disk: Disk,
+    pub memory_mb: u32,
+    pub cpus: u32,
+    /// Firmware enforces Secure Boot (x86 OVMF then needs SMM and a secure pflash)
+    pub secure_boot: bool,
+}
+
+/// Result of a single boot
+pub(crate) struct BootLog {
+    pub lines: Vec<String>,
+    /// Arrival time of each line, measured from QEMU start (host clock)
+    pub stamps: Vec<Duration>,
+    /// The `until` marker was seen before the deadline
+    pub reached_marker: bool,
+    pub timed_out: bool,
+}
+
+/// Boot `machine`, collecting serial lines until `until` appears, QEMU exits, or `timeout` passes.
+pub(crate) fn boot(
+    machine: &Machine,
+    work_dir: &Path,
+    timeout: Duration,
+    until: Option<&str>,
+    echo: bool,
+) -> Result<BootLog, String> {
+    fs::create_dir_all(work_dir).map_err(|e| format!("create {}: {e}", work_dir.display()))?;
+
+    let mut cmd = Command::new(&machine.qemu);
+    match machine.arch {
+        Arch::X86_64 if machine.secure_boot => cmd.args([
+            "-machine",
+            "q35,smm=on",
+            "-global",
+            "driver=cfi.pflash01,property=secure,value=on",
+        ]),
+        Arch::X86_64 => cmd.args(["-machine", "q35"]),
+        // virt's default CPU is 32-bit; TCG handles cortex-a72 everywhere
+        Arch::Aarch64 => cmd.args(["-machine", "virt", "-cpu", "cortex-a72"]),
+    };
+    cmd.args(["-display", "none", "-monitor", "none", "-no-reboot"])
+        .args(["-serial", "stdio"])
+        .args(["-m", &machine.memory_mb.to_string()])
+        .args(["-smp", &machine.cpus.to_string()]);
+
+    match &machine.firmware {
+        Firmware::Split { code, vars } => {
+            // OVMF writes to its vars store; never touch the system template
+            let vars_copy = work_dir.join("OVMF_VARS.fd");
+            fs::copy(vars, &vars_copy).map_err(|e| format!("copy {}: {e}", vars.display()))?;
+            cmd.arg("-drive").arg(format!(
+                "if=pflash,format=raw,readonly=on,file={}",
+                code.display()
+            ));
+            cmd.arg("-drive")
+                .arg(format!("if=pflash,format=raw,file={}", vars_copy.display()));
+        }
+        Firmware::Combined(bios) => {
+            cmd.arg("-bios").arg(bios);
+        }
+    }
+    // virt has no IDE controller; attach the disk over virtio there
+    let bus = match machine.arch {
+        Arch::X86_64 => "",
+        Arch::Aarch64 => "if=virtio,",
+    };
+    match &machine.disk {
+        Disk::EspDir(dir) => cmd
+            .arg("-drive")
+            .arg(format!("{bus}format=raw,file=fat:rw:{}", dir.display())),
+        Disk::Image(img) => cmd
+            .arg("-drive")
+            .arg(format!("{bus}format=raw,file={}", img.display())),
+    };
+
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit());
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("spawn {}: {e}", machine.qemu))?;
+
+    let stdout = child.stdout.take().ok_or("qemu stdout not captured")?;
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        // Raw lines: firmware output is not guaranteed UTF-8, and one bad byte must not end
+        // the capture
+        let mut reader = BufReader::new(stdout);
+        let mut raw = Vec::new();
+        loop {
+            raw.clear();
+            match reader.read_until(b'\n', &mut raw) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
+            let end = raw.strip_suffix(b"\n").unwrap_or(&raw);
+            if tx.send(String::from_utf8_lossy(end).into_owned()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let serial_path = work_dir.join("serial.log");
+    let mut serial = fs::File::create(&serial_path)
+        .map_err(|e| format!("create {}: {e}", serial_path.display()))?;
+
+    let started = Instant::now();
+    let deadline = started + timeout;
+    let mut log = BootLog {
+        lines: Vec::new(),
+        stamps: Vec::new(),
+        reached_marker: false,
+        timed_out: false,
+    };
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            log.timed_out = true;
+            break;
+        }
+        match rx.recv_timeout(left) {
+            Ok(line) => {
+                // Firmware consoles love CR/LF and ANSI noise; keep the log greppable
+                let line = strip_control(&line);
+                if echo {
+                    println!("    | {line}");
+                }
+                let _ = writeln!(serial, "{line}");
+                let hit = until.is_some_and(|m| line.contains(m));
+                log.lines.push(line);
+                log.stamps.push(started.elapsed());
+                if hit {
+                    log.reached_marker = true;
+                    break;
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                log.timed_out = true;
+                break;
+            }
+            // QEMU exited (e.g. -no-reboot after a reset or shutdown)
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
+    Ok(log)
+}
+
+fn strip_control(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch == '\u{1b}' {
+            // CSI sequence: ESC [ ... final byte in @..~
+            if chars.peek() == Some(&'[') {
+                chars.next();
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        if !ch.is_control() || ch == '\t' {
+            out.push(ch);
+        }
+    }
+    out.trim_end().to_string()
+}
@@ -0,0 +1,357 @@
+//! `cargo xtask release` — reproducible release artifacts, SHA-256 manifest, provenance.
+//!
+//! Builds run in a private target directory with the source, cargo home and target paths
+//! remapped, `SOURCE_DATE_EPOCH` pinned to the commit time and `--locked` dependencies (once
+//! Cargo.lock is committed), so the same commit and toolchain give byte-identical outputs on
+//! any machine. `--verify` rebuilds from scratch and compares against an existing
+//! `SHA256SUMS`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{serial::json_str, sha256, Arch, Flags};
+
+/// Host-side tools shipped next to the loader
+const HOST_TOOLS: &[&str] = &["zk-embed", "nonos-install"];
+
+const MANIFEST: &str = "SHA256SUMS";
+const PROVENANCE: &str = "provenance.json";
+
+struct Source {
+    commit: String,
+    epoch: String,
+    version: String,
+    /// Cargo.lock is committed, so dependencies are pinned (`--locked`) and recorded
+    locked: bool,
+}
+
+pub(crate) fn run(raw: &[String]) -> Result<(), String> {
+    let mut f = Flags::parse(raw, &["verify", "allow-dirty"])?;
+    let verify = f.switch("verify");
+    let allow_dirty = f.switch("allow-dirty");
+    let features = f.value("features");
+    let mut arches = f
+        .values("arch")
+        .iter()
+        .map(|a| Arch::parse(a))
+        .collect::<Result<Vec<_>, _>>()?;
+    let out = f.value("out").map(PathBuf::from);
+    let manifest = f.value("manifest").map(PathBuf::from);
+    f.finish()?;
+
+    if arches.is_empty() {
+        arches = vec![Arch::X86_64, Arch::Aarch64];
+    }
+    let src = source(allow_dirty)?;
+    let name = format!("nonos-boot-{}-{}", src.version, &src.commit[..12]);
+
+    if verify {
+        let manifest = manifest.unwrap_or_else(|| {
+            crate::xtask_dir()
+                .join("release")
+                .join(&name)
+                .join(MANIFEST)
+        });
+        let expected = fs::read_to_string(&manifest)
+            .map_err(|e| format!("read {}: {e}", manifest.display()))?;
+        let scratch = crate::xtask_dir().join("release-verify");
+        let built = build_all(
+            &src,
+            &arches,
+            features.as_deref(),
+            &scratch,
+            &scratch.join(&name),
+        )?;
+        return compare(&expected, &built, &manifest);
+    }
+
+    let out = out.unwrap_or_else(|| crate::xtask_dir().join("release").join(&name));
+    let target = crate::xtask_dir().join("release-build");
+    let sums = build_all(&src, &arches, features.as_deref(), &target, &out)?;
+
+    let manifest_text: String = sums
+        .iter()
+        .map(|(file, hash)| format!("{hash}  {file}\n"))
+        .collect();
+    write(&out.join(MANIFEST), manifest_text.as_bytes())?;
+    write(
+        &out.join(PROVENANCE),
+        provenance(&src, &arches, features.as_deref(), &sums)?.as_bytes(),
+    )?;
+
+    println!("[+] {} artifact(s) in {}", sums.len(), out.display());
+    for (file, hash) in &sums {
+        println!("    {hash}  {file}");
+    }
+    Ok(())
+}
+
+/// Commit id, commit time and package version; refuses a dirty tree unless allowed.
+fn source(allow_dirty: bool) -> Result<Source, String> {
+    let root = crate::workspace_root();
+    let git = |args: &[&str]| -> Result<String, String> {
+        let out = Command::new("git")
+            .current_dir(&root)
+            .args(args)
+            .output()
+            .map_err(|e| format!("spawn git: {e}"))?;
+        if !out.status.success() {
+            return Err(format!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&out.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+    };
+
+    let dirty = git(&["status", "--porcelain", "--untracked-files=no"])?;
+    if !dirty.is_empty() && !allow_dirty {
+        return Err("working tree has local changes; commit them or pass --allow-dirty".into());
+    }
+
+    // `--locked` and the provenance digest only mean something for the committed lock file
+    let locked = git(&["ls-files", "--error-unmatch", "Cargo.lock"]).is_ok();
+    if !locked {
+        println!("[-] Cargo.lock is not tracked: dependencies are resolved fresh and not pinned");
+        println!("    in the provenance; `git add -f Cargo.lock` for byte-for-byte rebuilds");
+    }
+
+    let manifest = root.join("Cargo.toml");
+    let text =
+        fs::read_to_string(&manifest).map_err(|e| format!("read {}: {e}", manifest.display()))?;
+    let version = text
+        .lines()
+        .find_map(|l| {
+            let v = l.trim().strip_prefix("version")?.trim().strip_prefix('=')?;
+            Some(v.trim().trim_matches('"').to_string())
+        })
+        .ok_or("no package version in Cargo.toml")?;
+
+    Ok(Source {
+        commit: git(&["rev-parse", "HEAD"])?,
+        epoch: git(&["log", "-1", "--format=%ct"])?,
+        version,
+        locked,
+    })
+}
+
+/// Build every artifact into `target`, copy them to `out`, return sorted (name, sha256).
+fn build_all(
+    src: &Source,
+    arches: &[Arch],
+    features: Option<&str>,
+    target: &Path,
+    out: &Path,
+) -> Result<Vec<(String, String)>, String> {
+    // Stale objects are the classic reproducibility leak; always start clean
+    for dir in [target, out] {
+        if dir.exists() {
+            fs::remove_dir_all(dir).map_err(|e| format!("clean {}: {e}", dir.display()))?;
+        }
+    }
+    fs::create_dir_all(out).map_err(|e| format!("create {}: {e}", out.display()))?;
+
+    let mut artifacts = Vec::new();
+    for &arch in arches {
+        let triple = arch.uefi_target();
+        let mut cmd = reproducible_cargo(src, target);
+        cmd.args(["build", "--release", "--package", "nonos_boot"])
+            .args(["--target", triple]);
+        if src.locked {
+            cmd.arg("--locked");
+        }
+        if let Some(f) = features {
+            cmd.args(["--features", f]);
+        }
+        println!("[*] building loader ({triple})");
+        crate::run_cmd(&mut cmd)?;
+        let built = target.join(triple).join("release").join("nonos_boot.efi");
+        artifacts.push((built, format!("nonos_boot-{}.efi", triple_arch(triple))));
+    }
+
+    let mut cmd = reproducible_cargo(src, target);
+    cmd.args(["build", "--release"]);
+    if src.locked {
+        cmd.arg("--locked");
+    }
+    for tool in HOST_TOOLS {
+        cmd.args(["--package", tool]);
+    }
+    println!("[*] building host tools ({})", HOST_TOOLS.join(", "));
+    crate::run_cmd(&mut cmd)?;
+    for tool in HOST_TOOLS {
+        let exe = format!("{tool}{}", env::consts::EXE_SUFFIX);
+        artifacts.push((target.join("release").join(&exe), exe));
+    }
+
+    let mut sums = Vec::new();
+    for (built, name) in artifacts {
+        let bytes = fs::read(&built).map_err(|e| format!("read {}: {e}", built.display()))?;
+        write(&out.join(&name), &bytes)?;
+        sums.push((name, sha256::digest_hex(&bytes)));
+    }
+    sums.sort();
+    Ok(sums)
+}
+
+fn triple_arch(triple: &str) -> &str {
+    triple.split('-').next().unwrap_or(triple)
+}
+
+/// cargo with every known source of path/time nondeterminism pinned
+fn reproducible_cargo(src: &Source, target: &Path) -> Command {
+    let root = crate::workspace_root();
+    let cargo_home = env::var("CARGO_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| home().join(".cargo"));
+    let rustflags = [
+        format!("--remap-path-prefix={}=/nonos-boot", root.display()),
+        format!("--remap-path-prefix={}=/cargo", cargo_home.display()),
+        format!("--remap-path-prefix={}=/target", target.display()),
+        "-Ccodegen-units=1".to_string(),
+    ]
+    .join("\x1f");
+
+    let mut cmd = crate::cargo();
+    cmd.current_dir(&root)
+        .env("CARGO_TARGET_DIR", target)
+        .env("SOURCE_DATE_EPOCH", &src.epoch)
+        // ASCII unit separator: the only RUSTFLAGS form that survives spaces in paths
+        .env("CARGO_ENCODED_RUSTFLAGS", rustflags)
+        .env_remove("RUSTFLAGS")
+        .env("TZ", "UTC")
+        .env("LC_ALL", "C");
+    cmd
+}
+
+fn home() -> PathBuf {
+    env::var_os("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/"))
+}
+
+/// Rebuilt hashes against a manifest; every listed file must exist and match.
+fn compare(expected: &str, built: &[(String, String)], manifest: &Path) -> Result<(), String> {
+    let mut bad = 0usize;
+    let mut seen = 0usize;
+    for line in expected.lines().filter(|l| !l.trim().is_empty()) {
+        let Some((hash, file)) = line.split_once("  ") else {
+            return Err(format!("{}: malformed line `{line}`", manifest.display()));
+        };
+        seen += 1;
+        match built.iter().find(|(n, _)| n == file) {
+            Some((_, h)) if h == hash => println!("[+] {file}: reproduced"),
+            Some((_, h)) => {
+                bad += 1;
+                println!("[-] {file}: MISMATCH");
+                println!("      manifest {hash}");
+                println!("      rebuilt  {h}");
+            }
+            None => {
+                bad += 1;
+                println!("[-] {file}: not produced by this build");
+            }
+        }
+    }
+    for (file, _) in built {
+        if !expected.lines().any(|l| l.ends_with(&format!("  {file}"))) {
+            println!("[*] {file}: built but not in manifest");
+        }
+    }
+
+    if bad == 0 {
+        println!("[+] {seen} artifact(s) reproduced bit-for-bit");
+        Ok(())
+    } else {
+        Err(format!("{bad} of {seen} artifact(s) did not reproduce"))
+    }
+}
+
+/// in-toto statement with a SLSA v1 provenance predicate (unsigned; sign it with the
+/// release key out of band).
+fn provenance(
+    src: &Source,
+    arches: &[Arch],
+    features: Option<&str>,
+    sums: &[(String, String)],
+) -> Result<String, String> {
+    // An untracked lock file says nothing about what was resolved; leave it out
+    let lock_dep = if src.locked {
+        let lock = crate::workspace_root().join("Cargo.lock");
+        let hash = fs::read(&lock)
+            .map(|b| sha256::digest_hex(&b))
+            .map_err(|e| format!("read {}: {e}", lock.display()))?;
+        format!(",\n        {{\"name\": \"Cargo.lock\", \"digest\": {{\"sha256\": \"{hash}\"}}}}")
+    } else {
+        String::new()
+    };
+    let tool_version = |bin: &str| -> String {
+        Command::new(bin)
+            .arg("-V")
+            .output()
+            .ok()
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+            .unwrap_or_default()
+    };
+
+    let subjects: Vec<String> = sums
+        .iter()
+        .map(|(n, h)| format!("    {{\"name\": \"{n}\", \"digest\": {{\"sha256\": \"{h}\"}}}}"))
+        .collect();
+    let targets: Vec<String> = arches
+        .iter()
+        .map(|a| format!("\"{}\"", a.uefi_target()))
+        .collect();
+
+    Ok(format!(
+        r#"{{
+  "_type": "https://in-toto.io/Statement/v1",
+  "subject": [
+{subjects}
+  ],
+  "predicateType": "https://slsa.dev/provenance/v1",
+  "predicate": {{
+    "buildDefinition": {{
+      "buildType": "https://nonos.systems/build/xtask-release/v1",
+      "externalParameters": {{
+        "targets": [{targets}],
+        "features": {features},
+        "hostTools": [{tools}]
+      }},
+      "internalParameters": {{
+        "SOURCE_DATE_EPOCH": "{epoch}",
+        "rustc": {rustc},
+        "cargo": {cargo}
+      }},
+      "resolvedDependencies": [
+        {{"uri": "git+https://github.com/NON-OS/nonos-boot", "digest": {{"gitCommit": "{commit}"}}}}{lock_dep}
+      ]
+    }},
+    "runDetails": {{
+      "builder": {{"id": "https://nonos.systems/build/xtask-release"}}
+    }}
+  }}
+}}
+"#,
+        subjects = subjects.join(",\n"),
+        targets = targets.join(", "),
+        features = json_str(features.unwrap_or("default")),
+        tools = HOST_TOOLS
+            .iter()
+            .map(|t| format!("\"{t}\""))
+            .collect::<Vec<_>>()
+            .join(", "),
+        epoch = src.epoch,
+        rustc = json_str(&tool_version("rustc")),
+        cargo = json_str(&tool_version("cargo")),
+        commit = src.commit,
+    ))
+}
+
+fn write(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    fs::write(path, bytes).map_err(|e| format!("write {}: {e}", path.display()))
+}
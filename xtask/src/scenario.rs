@@ -0,0 +1,125 @@
+//! Boot scenario files: what the serial console must (and must not) show.
+//!
+//! One directive per line; lines starting with `#` are comments.
+//!
+//! ```text
+//! timeout 120      seconds before the boot is declared hung
+//! until   <text>   stop the VM once a line contains <text>
+//! expect  <text>   must appear, in file order relative to other `expect`s
+//! forbid  <text>   must not appear anywhere
+//! ```
+//!
+//! `expect` and `until` lines on structured records (`@nonos|...`) only hold once the loader
+//! emits them; test-boot enforces them with `--expect-markers` and drops them otherwise. A
+//! scenario left with no `expect` or `until` is then reported skipped, which fails the run.
+
+use std::{fs, path::Path, time::Duration};
+
+use crate::serial;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 120;
+
+pub(crate) struct Scenario {
+    pub name: String,
+    pub timeout: Duration,
+    pub until: Option<String>,
+    pub expect: Vec<String>,
+    pub forbid: Vec<String>,
+}
+
+impl Scenario {
+    pub(crate) fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("read {}: {e}", path.display()))?;
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "scenario".to_string());
+        Self::parse(&name, &text).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    fn parse(name: &str, text: &str) -> Result<Self, String> {
+        let mut sc = Scenario {
+            name: name.to_string(),
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            until: None,
+            expect: Vec::new(),
+            forbid: Vec::new(),
+        };
+
+        for (n, raw) in text.lines().enumerate() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+            if rest.is_empty() {
+                return Err(format!("line {}: `{key}` needs an argument", n + 1));
+            }
+            match key {
+                "timeout" => {
+                    let secs = rest
+                        .parse::<u64>()
+                        .map_err(|_| format!("line {}: bad timeout `{rest}`", n + 1))?;
+                    sc.timeout = Duration::from_secs(secs);
+                }
+                "until" => sc.until = Some(rest.to_string()),
+                "expect" => sc.expect.push(rest.to_string()),
+                "forbid" => sc.forbid.push(rest.to_string()),
+                other => return Err(format!("line {}: unknown directive `{other}`", n + 1)),
+            }
+        }
+        Ok(sc)
+    }
+
+    /// Drop the `expect`/`until` lines on structured records; returns how many were dropped.
+    /// What is left may assert nothing a boot has to reach; see [`Scenario::asserts_progress`].
+    pub(crate) fn skip_markers(&mut self) -> usize {
+        let before = self.expect.len();
+        self.expect.retain(|e| !e.contains(serial::PREFIX));
+        let mut skipped = before - self.expect.len();
+        if self
+            .until
+            .as_deref()
+            .is_some_and(|u| u.contains(serial::PREFIX))
+        {
+            self.until = None;
+            skipped += 1;
+        }
+        skipped
+    }
+
+    /// Something must appear for the scenario to pass; with only `forbid` lines a hung or
+    /// silent boot would pass too.
+    pub(crate) fn asserts_progress(&self) -> bool {
+        !self.expect.is_empty() || self.until.is_some()
+    }
+
+    /// Check a captured serial log; returns one message per failed assertion.
+    pub(crate) fn check(&self, lines: &[String]) -> Vec<String> {
+        let mut failures = Vec::new();
+
+        // Ordered, but a later expectation may match the same line as the previous one
+        let mut cursor = 0;
+        for want in &self.expect {
+            match lines[cursor..]
+                .iter()
+                .position(|l| l.contains(want.as_str()))
+            {
+                Some(i) => cursor += i,
+                None => failures.push(format!("expected `{want}` (from line {})", cursor + 1)),
+            }
+        }
+
+        for bad in &self.forbid {
+            if let Some((i, l)) = lines
+                .iter()
+                .enumerate()
+                .find(|(_, l)| l.contains(bad.as_str()))
+            {
+                failures.push(format!("forbidden `{bad}` at line {}: {l}", i + 1));
+            }
+        }
+        failures
+    }
+}
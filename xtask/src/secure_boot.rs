@@ -0,0 +1,210 @@
+//! Secure Boot test profile: throwaway PK/KEK/db keys, enrolled firmware vars, signed loader.
+//!
+//! External tools: `openssl` (key generation), `sbsign` from sbsigntool (Authenticode
+//! signatures) and `virt-fw-vars` from python virt-firmware (variable store enrollment).
+//! Keys are created once under `target/xtask/sb-keys` and reused, so repeated runs only
+//! re-sign and re-enroll. They are test material; nothing here is meant for real machines.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{qemu::Firmware, Arch, Flags};
+
+/// Owner GUID recorded with every enrolled certificate (arbitrary, fixed for diffable vars)
+const OWNER_GUID: &str = "4e4f4e4f-5342-4b65-9973-746573746b65";
+
+/// `(name, issuer)`: PK is self-signed, PK signs KEK, KEK signs db, db signs binaries
+const KEYS: &[(&str, Option<&str>)] = &[("PK", None), ("KEK", Some("PK")), ("db", Some("KEK"))];
+
+const TOOLS_HINT: &str = "the Secure Boot profile needs openssl, sbsign (sbsigntool) \
+                          and virt-fw-vars (python3-virt-firmware) on PATH";
+
+pub(crate) struct SecureBoot {
+    keys: PathBuf,
+    /// shim to chain through; the loader then becomes its second stage
+    shim: Option<PathBuf>,
+}
+
+impl SecureBoot {
+    pub(crate) const SWITCHES: &'static [&'static str] = &["secure-boot"];
+
+    /// `Some` when `--secure-boot` was given.
+    pub(crate) fn from_flags(f: &mut Flags) -> Result<Option<Self>, String> {
+        let on = f.switch("secure-boot");
+        let keys = f.value("sb-keys").map(PathBuf::from);
+        let shim = f.value("shim").map(PathBuf::from);
+        if !on {
+            return match (keys, shim) {
+                (None, None) => Ok(None),
+                _ => Err("--sb-keys/--shim only apply with --secure-boot".into()),
+            };
+        }
+        Ok(Some(Self {
+            keys: keys.unwrap_or_else(|| crate::xtask_dir().join("sb-keys")),
+            shim,
+        }))
+    }
+
+    /// Create any missing key pair (`<NAME>.key` + `<NAME>.crt`, issued by the key above it).
+    /// A regenerated issuer invalidates everything below it, so those are reissued too.
+    pub(crate) fn ensure_keys(&self) -> Result<(), String> {
+        fs::create_dir_all(&self.keys)
+            .map_err(|e| format!("create {}: {e}", self.keys.display()))?;
+        let mut reissue = false;
+        for &(name, issuer) in KEYS {
+            let (key, crt) = self.pair(name);
+            if !reissue && key.is_file() && crt.is_file() {
+                continue;
+            }
+            reissue = true;
+            println!("[*] generating test {name} key");
+            let subj = format!("/CN=NONOS test {name}/");
+            let Some(issuer) = issuer else {
+                tool(
+                    Command::new("openssl")
+                        .args([
+                            "req", "-new", "-x509", "-newkey", "rsa:2048", "-nodes", "-sha256",
+                        ])
+                        .args(["-days", "3650", "-subj", &subj])
+                        .arg("-keyout")
+                        .arg(&key)
+                        .arg("-out")
+                        .arg(&crt),
+                )?;
+                continue;
+            };
+
+            // Anything that issues a certificate itself has to be marked a CA to verify
+            let csr = self.keys.join(format!("{name}.csr"));
+            let mut req = Command::new("openssl");
+            req.args(["req", "-new", "-newkey", "rsa:2048", "-nodes", "-sha256"])
+                .args(["-subj", &subj]);
+            if KEYS.iter().any(|&(_, i)| i == Some(name)) {
+                req.args(["-addext", "basicConstraints=critical,CA:TRUE"]);
+            }
+            tool(req.arg("-keyout").arg(&key).arg("-out").arg(&csr))?;
+            let (ca_key, ca_crt) = self.pair(issuer);
+            tool(
+                Command::new("openssl")
+                    // Each issuer signs exactly one certificate
+                    .args(["x509", "-req", "-sha256", "-days", "3650"])
+                    .args(["-set_serial", "1", "-copy_extensions", "copy"])
+                    .arg("-in")
+                    .arg(&csr)
+                    .arg("-CA")
+                    .arg(&ca_crt)
+                    .arg("-CAkey")
+                    .arg(&ca_key)
+                    .arg("-out")
+                    .arg(&crt),
+            )?;
+            let _ = fs::remove_file(&csr);
+        }
+        Ok(())
+    }
+
+    /// Files to place on the ESP, given the unsigned loader: `(signed binary, ESP path)`.
+    pub(crate) fn signed_boot_files(
+        &self,
+        arch: Arch,
+        efi: &Path,
+        work: &Path,
+    ) -> Result<Vec<(PathBuf, String)>, String> {
+        let out = work.join("signed");
+        fs::create_dir_all(&out).map_err(|e| format!("create {}: {e}", out.display()))?;
+
+        let loader = out.join("nonos_boot.efi");
+        self.sign(efi, &loader)?;
+        let Some(shim) = &self.shim else {
+            return Ok(vec![(loader, arch.boot_file().to_string())]);
+        };
+
+        // shim looks for its second stage next to itself under the GRUB name
+        let signed_shim = out.join("shim.efi");
+        self.sign(shim, &signed_shim)?;
+        let second = match arch {
+            Arch::X86_64 => "EFI/BOOT/grubx64.efi",
+            Arch::Aarch64 => "EFI/BOOT/grubaa64.efi",
+        };
+        Ok(vec![
+            (signed_shim, arch.boot_file().to_string()),
+            (loader, second.to_string()),
+        ])
+    }
+
+    /// Firmware with our keys enrolled into a copy of the vars template under `work`.
+    pub(crate) fn enrolled(&self, firmware: &Firmware, work: &Path) -> Result<Firmware, String> {
+        let Firmware::Split { code, vars } = firmware else {
+            return Err("Secure Boot needs split CODE/VARS firmware; pass --ovmf-vars".into());
+        };
+        fs::create_dir_all(work).map_err(|e| format!("create {}: {e}", work.display()))?;
+        let enrolled = work.join("VARS.enrolled.fd");
+        let (_, pk) = self.pair("PK");
+        let (_, kek) = self.pair("KEK");
+        let (_, db) = self.pair("db");
+
+        println!("[*] enrolling test keys into {}", enrolled.display());
+        tool(
+            Command::new("virt-fw-vars")
+                .arg("--input")
+                .arg(vars)
+                .arg("--output")
+                .arg(&enrolled)
+                .arg("--set-pk")
+                .arg(OWNER_GUID)
+                .arg(&pk)
+                .arg("--add-kek")
+                .arg(OWNER_GUID)
+                .arg(&kek)
+                .arg("--add-db")
+                .arg(OWNER_GUID)
+                .arg(&db)
+                .arg("--secure-boot"),
+        )?;
+
+        Ok(Firmware::Split {
+            code: code.clone(),
+            vars: enrolled,
+        })
+    }
+
+    fn sign(&self, input: &Path, output: &Path) -> Result<(), String> {
+        let (key, crt) = self.pair("db");
+        tool(
+            Command::new("sbsign")
+                .arg("--key")
+                .arg(&key)
+                .arg("--cert")
+                .arg(&crt)
+                .arg("--output")
+                .arg(output)
+                .arg(input),
+        )
+    }
+
+    fn pair(&self, name: &str) -> (PathBuf, PathBuf) {
+        (
+            self.keys.join(format!("{name}.key")),
+            self.keys.join(format!("{name}.crt")),
+        )
+    }
+}
+
+/// Run an external tool quietly; on failure show its output and what to install.
+fn tool(cmd: &mut Command) -> Result<(), String> {
+    let name = cmd.get_program().to_string_lossy().into_owned();
+    let out = cmd
+        .output()
+        .map_err(|e| format!("spawn {name}: {e} ({TOOLS_HINT})"))?;
+    if out.status.success() {
+        return Ok(());
+    }
+    Err(format!(
+        "{name} failed ({}):\n{}",
+        out.status,
+        String::from_utf8_lossy(&out.stderr).trim_end()
+    ))
+}
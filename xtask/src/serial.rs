@@ -0,0 +1,244 @@
+//! Structured serial log lines and `cargo xtask serial` (capture, colorize, export JSON).
+//!
+//! The loader emits one record per line:
+//!
+//! ```text
+//! @nonos|<level>|<phase>|<code>|<message>
+//! @nonos|I|verify|ZK_OK|proof verified for ATTEST_V1
+//! ```
+//!
+//! `level` is one of `E W I D T`; `phase` and `code` are short tokens without `|`; the
+//! message runs to the end of the line and may contain anything. Lines without the
+//! `@nonos|` prefix (firmware chatter) are kept as unstructured text.
+
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, BufRead, BufReader, IsTerminal, Read, Write},
+    path::PathBuf,
+};
+
+use crate::Flags;
+
+pub(crate) const PREFIX: &str = "@nonos|";
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "E" => Level::Error,
+            "W" => Level::Warn,
+            "I" => Level::Info,
+            "D" => Level::Debug,
+            "T" => Level::Trace,
+            _ => return None,
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+
+    fn color(self) -> &'static str {
+        match self {
+            Level::Error => "\x1b[1;31m",
+            Level::Warn => "\x1b[1;33m",
+            Level::Info => "\x1b[0;32m",
+            Level::Debug => "\x1b[0;34m",
+            Level::Trace => "\x1b[2m",
+        }
+    }
+}
+
+pub(crate) struct Record {
+    /// 1-based line number in the capture
+    pub line: usize,
+    pub level: Level,
+    pub phase: String,
+    pub code: String,
+    pub message: String,
+}
+
+/// Parse one structured line; `None` for anything that is not a well-formed record.
+pub(crate) fn parse_line(line_no: usize, line: &str) -> Option<Record> {
+    // Records may be preceded by firmware output on the same line
+    let start = line.find(PREFIX)?;
+    let mut parts = line[start + PREFIX.len()..].splitn(4, '|');
+    let level = Level::parse(parts.next()?)?;
+    let phase = parts.next()?;
+    let code = parts.next()?;
+    let message = parts.next()?;
+    if phase.is_empty() || code.is_empty() {
+        return None;
+    }
+    Some(Record {
+        line: line_no,
+        level,
+        phase: phase.to_string(),
+        code: code.to_string(),
+        message: message.to_string(),
+    })
+}
+
+pub(crate) fn parse_all(lines: &[String]) -> Vec<Record> {
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, l)| parse_line(i + 1, l))
+        .collect()
+}
+
+/// JSON array of records (hand-rolled; xtask has no serde)
+pub(crate) fn to_json(records: &[Record]) -> String {
+    let mut out = String::from("[\n");
+    for (i, r) in records.iter().enumerate() {
+        let _ = write!(
+            out,
+            "  {{\"line\": {}, \"level\": \"{}\", \"phase\": {}, \"code\": {}, \"message\": {}}}",
+            r.line,
+            r.level.name(),
+            json_str(&r.phase),
+            json_str(&r.code),
+            json_str(&r.message)
+        );
+        out.push_str(if i + 1 == records.len() { "\n" } else { ",\n" });
+    }
+    out.push_str("]\n");
+    out
+}
+
+pub(crate) fn json_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// `cargo xtask serial` — read a capture (file, tty or stdin) and render/export it.
+pub(crate) fn run(raw: &[String]) -> Result<(), String> {
+    let mut f = Flags::parse(raw, &["no-color", "only-records"])?;
+    let no_color = f.switch("no-color");
+    let only_records = f.switch("only-records");
+    let input = f.value("input").unwrap_or_else(|| "-".to_string());
+    let json = f.value("json").map(PathBuf::from);
+    let tee = f.value("tee").map(PathBuf::from);
+    let fail_on = f
+        .value("fail-on")
+        .map(|l| parse_level_name(&l))
+        .transpose()?;
+    f.finish()?;
+
+    let reader: Box<dyn Read> = if input == "-" {
+        Box::new(io::stdin())
+    } else {
+        // Works for plain files and for already-configured serial devices alike
+        Box::new(fs::File::open(&input).map_err(|e| format!("open {input}: {e}"))?)
+    };
+    let mut tee = match &tee {
+        Some(p) => Some(fs::File::create(p).map_err(|e| format!("create {}: {e}", p.display()))?),
+        None => None,
+    };
+
+    let color = !no_color && io::stdout().is_terminal();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut records = Vec::new();
+
+    // Raw lines, so serial noise that is not UTF-8 neither aborts the capture nor gets
+    // rewritten in the --tee copy; only the parsed view is lossy
+    let mut reader = BufReader::new(reader);
+    let mut raw = Vec::new();
+    for i in 0.. {
+        raw.clear();
+        let n = reader
+            .read_until(b'\n', &mut raw)
+            .map_err(|e| format!("read {input}: {e}"))?;
+        if n == 0 {
+            break;
+        }
+        if let Some(t) = tee.as_mut() {
+            let _ = t.write_all(&raw);
+        }
+        let lossy = String::from_utf8_lossy(&raw);
+        let line = lossy.trim_end_matches('\n').trim_end_matches('\r');
+        match parse_line(i + 1, line) {
+            Some(r) => {
+                let _ = writeln!(out, "{}", render(&r, color));
+                records.push(r);
+            }
+            None if only_records => {}
+            None if color => {
+                let _ = writeln!(out, "\x1b[2m{line}\x1b[0m");
+            }
+            None => {
+                let _ = writeln!(out, "{line}");
+            }
+        }
+    }
+
+    if let Some(path) = &json {
+        fs::write(path, to_json(&records)).map_err(|e| format!("write {}: {e}", path.display()))?;
+    }
+
+    if let Some(threshold) = fail_on {
+        let bad = records.iter().filter(|r| r.level <= threshold).count();
+        if bad > 0 {
+            return Err(format!(
+                "{bad} record(s) at level {} or worse",
+                threshold.name()
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn parse_level_name(s: &str) -> Result<Level, String> {
+    match s {
+        "error" => Ok(Level::Error),
+        "warn" => Ok(Level::Warn),
+        "info" => Ok(Level::Info),
+        _ => Err(format!("--fail-on expects error|warn|info, got `{s}`")),
+    }
+}
+
+fn render(r: &Record, color: bool) -> String {
+    let tag = r.level.name().to_ascii_uppercase();
+    if color {
+        format!(
+            "{}{tag:<5}\x1b[0m \x1b[1m{:<12}\x1b[0m {:<14} {}",
+            r.level.color(),
+            r.phase,
+            r.code,
+            r.message
+        )
+    } else {
+        format!("{tag:<5} {:<12} {:<14} {}", r.phase, r.code, r.message)
+    }
+}
@@ -0,0 +1,291 @@
+//! `cargo xtask size` — build the loader across a feature matrix and enforce size budgets.
+//!
+//! Budget file (one configuration per line, `#` comments):
+//!
+//! ```text
+//! base        default                                max=1536K
+//! zk-groth16  +zk-groth16,zk-vk-provisioned          max=2560K delta=1024K
+//! release     +zk-groth16,zk-vk-provisioned,efi-rng  max=3M
+//! ```
+//!
+//! The first line is the baseline. `+features` means "baseline plus these"; anything else
+//! is the full feature list. `max` limits the file size, `delta` the growth over the
+//! baseline. With `--symbols`, `cargo bloat --crates` attributes the growth to crates.
+
+use std::{collections::BTreeMap, fmt::Write as _, fs, path::PathBuf};
+
+use crate::{image::parse_size, Arch, Flags};
+
+const DEFAULT_BUDGET: &str = "xtask/budgets/size.budget";
+
+/// Crate deltas smaller than this are noise in the per-crate breakdown
+const CRATE_DELTA_MIN: i64 = 1024;
+
+struct Config {
+    name: String,
+    features: String,
+    max: Option<u64>,
+    delta: Option<u64>,
+}
+
+struct Measured {
+    file: u64,
+    /// PE section name → raw size
+    sections: Vec<(String, u64)>,
+    /// crate → bytes of code, when `--symbols`
+    crates: BTreeMap<String, u64>,
+}
+
+pub(crate) fn run(raw: &[String]) -> Result<(), String> {
+    let mut f = Flags::parse(raw, &["symbols"])?;
+    let symbols = f.switch("symbols");
+    let arch = Arch::from_flags(&mut f)?;
+    let budget = f
+        .value("budget")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| crate::workspace_root().join(DEFAULT_BUDGET));
+    let only = f.values("only");
+    f.finish()?;
+
+    let mut configs = load(&budget)?;
+    if !only.is_empty() {
+        // The baseline is always needed for deltas
+        let base = configs.remove(0);
+        configs.retain(|c| only.contains(&c.name));
+        configs.insert(0, base);
+    }
+
+    let mut results: Vec<(Config, Measured)> = Vec::new();
+    for cfg in configs {
+        println!("[*] {} ({})", cfg.name, cfg.features);
+        let efi = crate::build_efi(arch, true, Some(&cfg.features))?;
+        let bytes = fs::read(&efi).map_err(|e| format!("read {}: {e}", efi.display()))?;
+        let crates = if symbols {
+            bloat_crates(arch, &cfg.features)?
+        } else {
+            BTreeMap::new()
+        };
+        results.push((
+            cfg,
+            Measured {
+                file: bytes.len() as u64,
+                sections: pe_sections(&bytes),
+                crates,
+            },
+        ));
+    }
+
+    let base = &results[0].1;
+    let mut report = String::from("# config\tfeatures\tbytes\tdelta\tmax\tmax_delta\tstatus\n");
+    let mut over = Vec::new();
+    println!();
+    println!(
+        "{:<14} {:>10} {:>10} {:>10} {:>10}  status",
+        "config", "size", "delta K", "max", "max delta"
+    );
+    for (cfg, m) in &results {
+        let delta = m.file as i64 - base.file as i64;
+        let mut why = Vec::new();
+        if let Some(max) = cfg.max.filter(|&max| m.file > max) {
+            why.push(format!("{} over max", m.file - max));
+        }
+        if let Some(limit) = cfg.delta.filter(|&l| delta > l as i64) {
+            why.push(format!("delta {} over {}", delta - limit as i64, limit));
+        }
+        let status = if why.is_empty() {
+            "ok".to_string()
+        } else {
+            why.join(", ")
+        };
+        println!(
+            "{:<14} {:>10} {:>+10} {:>10} {:>10}  {status}",
+            cfg.name,
+            kib(m.file),
+            delta / 1024,
+            cfg.max.map(kib).unwrap_or_else(|| "-".into()),
+            cfg.delta.map(kib).unwrap_or_else(|| "-".into()),
+        );
+        let _ = writeln!(
+            report,
+            "{}\t{}\t{}\t{delta}\t{}\t{}\t{}",
+            cfg.name,
+            cfg.features,
+            m.file,
+            cfg.max.map(|v| v.to_string()).unwrap_or_default(),
+            cfg.delta.map(|v| v.to_string()).unwrap_or_default(),
+            if why.is_empty() { "ok" } else { "over" }
+        );
+        if !why.is_empty() {
+            over.push(cfg.name.clone());
+        }
+    }
+
+    println!();
+    for (cfg, m) in &results[1..] {
+        let sections: Vec<String> = m
+            .sections
+            .iter()
+            .map(|(name, size)| {
+                let was = base
+                    .sections
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map(|(_, s)| *s)
+                    .unwrap_or(0);
+                format!("{name} {:+}", *size as i64 - was as i64)
+            })
+            .collect();
+        println!("    {:<14} sections: {}", cfg.name, sections.join(", "));
+        if symbols {
+            let mut grew: Vec<(String, i64)> = m
+                .crates
+                .iter()
+                .map(|(c, s)| {
+                    (
+                        c.clone(),
+                        *s as i64 - *base.crates.get(c).unwrap_or(&0) as i64,
+                    )
+                })
+                .filter(|(_, d)| d.abs() >= CRATE_DELTA_MIN)
+                .collect();
+            grew.sort_by_key(|(_, d)| -d);
+            for (c, d) in grew.iter().take(8) {
+                println!("    {:<14}   {c:<24} {:+} KiB", "", d / 1024);
+            }
+        }
+    }
+
+    let dir = crate::xtask_dir().join("size");
+    fs::create_dir_all(&dir).map_err(|e| format!("create {}: {e}", dir.display()))?;
+    let path = dir.join("report.tsv");
+    fs::write(&path, report).map_err(|e| format!("write {}: {e}", path.display()))?;
+    println!("[+] report: {}", path.display());
+
+    if over.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("over budget: {}", over.join(", ")))
+    }
+}
+
+fn load(path: &std::path::Path) -> Result<Vec<Config>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("read {}: {e}", path.display()))?;
+    let mut configs: Vec<Config> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let at = |msg: String| format!("{}:{}: {msg}", path.display(), i + 1);
+        let mut words = line.split_whitespace();
+        let (Some(name), Some(features)) = (words.next(), words.next()) else {
+            return Err(at(
+                "expected `<name> <features> [max=SIZE] [delta=SIZE]`".into()
+            ));
+        };
+        let features = match (features.strip_prefix('+'), configs.first()) {
+            (Some(extra), Some(base)) => format!("{},{extra}", base.features),
+            (Some(_), None) => return Err(at("the baseline cannot extend itself".into())),
+            (None, _) => features.to_string(),
+        };
+        let mut cfg = Config {
+            name: name.to_string(),
+            features,
+            max: None,
+            delta: None,
+        };
+        for kv in words {
+            match kv.split_once('=') {
+                Some(("max", v)) => cfg.max = Some(parse_size(v).map_err(at)?),
+                Some(("delta", v)) => cfg.delta = Some(parse_size(v).map_err(at)?),
+                _ => return Err(at(format!("unknown budget `{kv}`"))),
+            }
+        }
+        configs.push(cfg);
+    }
+    if configs.is_empty() {
+        return Err(format!("{}: no configurations", path.display()));
+    }
+    Ok(configs)
+}
+
+/// (name, raw size) of each section in a PE image; empty if the headers don't parse.
+fn pe_sections(b: &[u8]) -> Vec<(String, u64)> {
+    let u16_at = |o: usize| b.get(o..o + 2).map(|s| u16::from_le_bytes([s[0], s[1]]));
+    let u32_at = |o: usize| {
+        b.get(o..o + 4)
+            .map(|s| u32::from_le_bytes([s[0], s[1], s[2], s[3]]))
+    };
+    let Some(pe) = u32_at(0x3c).map(|v| v as usize) else {
+        return Vec::new();
+    };
+    if b.get(pe..pe + 4) != Some(b"PE\0\0") {
+        return Vec::new();
+    }
+    let (Some(count), Some(opt_size)) = (u16_at(pe + 6), u16_at(pe + 20)) else {
+        return Vec::new();
+    };
+    let table = pe + 24 + opt_size as usize;
+    (0..count as usize)
+        .filter_map(|i| {
+            let h = table + i * 40;
+            let name = b.get(h..h + 8)?;
+            let name = String::from_utf8_lossy(name)
+                .trim_end_matches('\0')
+                .to_string();
+            Some((name, u32_at(h + 16)? as u64))
+        })
+        .collect()
+}
+
+/// Per-crate code size from `cargo bloat --crates` (needs `cargo install cargo-bloat`).
+fn bloat_crates(arch: Arch, features: &str) -> Result<BTreeMap<String, u64>, String> {
+    let out = crate::cargo()
+        .current_dir(crate::workspace_root())
+        .args([
+            "bloat",
+            "--release",
+            "--crates",
+            "-n",
+            "0",
+            "--message-format",
+            "json",
+        ])
+        .args(["--package", "nonos_boot", "--target", arch.uefi_target()])
+        .args(["--features", features])
+        .output()
+        .map_err(|e| format!("spawn cargo bloat: {e}"))?;
+    if !out.status.success() {
+        return Err(format!(
+            "cargo bloat failed (install with `cargo install cargo-bloat`): {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        ));
+    }
+    Ok(parse_bloat(&String::from_utf8_lossy(&out.stdout)))
+}
+
+/// Pull `{"name": .., "size": ..}` pairs out of cargo-bloat's JSON without a JSON parser.
+fn parse_bloat(json: &str) -> BTreeMap<String, u64> {
+    let mut out = BTreeMap::new();
+    let Some(start) = json.find("\"crates\"") else {
+        return out;
+    };
+    for obj in json[start..].split('{').skip(1) {
+        let field = |key: &str| -> Option<&str> {
+            let rest = &obj[obj.find(&format!("\"{key}\""))? + key.len() + 2..];
+            let rest = rest.trim_start().strip_prefix(':')?.trim_start();
+            let end = rest.find([',', '}']).unwrap_or(rest.len());
+            Some(rest[..end].trim().trim_matches('"'))
+        };
+        if let (Some(name), Some(size)) = (field("name"), field("size")) {
+            if let Ok(size) = size.parse() {
+                out.insert(name.to_string(), size);
+            }
+        }
+    }
+    out
+}
+
+fn kib(v: u64) -> String {
+    format!("{}K", v.div_ceil(1024))
+}
@@ -0,0 +1,290 @@
+//! `cargo xtask test-boot` — build, assemble an ESP, boot under QEMU+OVMF, assert on serial.
+
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    image::{self, Layout},
+    qemu::{self, Disk, Firmware, Machine},
+    scenario::Scenario,
+    secure_boot::SecureBoot,
+    serial, Arch, Flags,
+};
+
+/// Where the loader's inputs land inside the ESP (the loader itself goes to `Arch::boot_file`)
+const ESP_KERNEL: &str = "EFI/nonos/nonos_kernel.efi";
+const ESP_CAPSULE: &str = "EFI/nonos/capsule.caps";
+const ESP_CONFIG: &str = "EFI/nonos/boot.cfg";
+
+pub(crate) const DEFAULT_SCENARIO: &str = "xtask/scenarios/boot-ok.scenario";
+const SECURE_BOOT_SCENARIO: &str = "xtask/scenarios/secure-boot.scenario";
+/// Run last under the Secure Boot profile, against an unsigned copy of the loader
+const SB_UNSIGNED_SCENARIO: &str = "xtask/scenarios/sb-unsigned.scenario";
+
+/// Loader build and ESP inputs shared by every command that boots the loader
+pub(crate) struct BootInputs {
+    pub arch: Arch,
+    release: bool,
+    features: Option<String>,
+    prebuilt: Option<PathBuf>,
+    kernel: Option<PathBuf>,
+    capsule: Option<PathBuf>,
+    config: Option<PathBuf>,
+    layout: Option<PathBuf>,
+    vars: BTreeMap<String, String>,
+    pub qemu: String,
+}
+
+impl BootInputs {
+    /// Switches consumed by [`BootInputs::from_flags`]
+    pub(crate) const SWITCHES: &'static [&'static str] = &["release"];
+
+    pub(crate) fn from_flags(f: &mut Flags) -> Result<Self, String> {
+        let arch = Arch::from_flags(f)?;
+        Ok(Self {
+            arch,
+            release: f.switch("release"),
+            features: f.value("features"),
+            prebuilt: f.value("efi").map(PathBuf::from),
+            kernel: f.value("kernel").map(PathBuf::from),
+            capsule: f.value("capsule").map(PathBuf::from),
+            config: f.value("config").map(PathBuf::from),
+            layout: f.value("layout").map(PathBuf::from),
+            vars: image::parse_vars(f.values("var"))?,
+            qemu: f
+                .value("qemu")
+                .or_else(|| env::var("QEMU").ok())
+                .unwrap_or_else(|| arch.default_qemu().to_string()),
+        })
+    }
+
+    /// Build the loader, or take the prebuilt one.
+    pub(crate) fn loader(&self) -> Result<PathBuf, String> {
+        match &self.prebuilt {
+            Some(p) => Ok(p.clone()),
+            None => crate::build_efi(self.arch, self.release, self.features.as_deref()),
+        }
+    }
+
+    /// Lay out the boot disk under `work`; under Secure Boot the loader (and shim) are signed.
+    pub(crate) fn prepare(
+        &self,
+        efi: &Path,
+        work: &Path,
+        sb: Option<&SecureBoot>,
+    ) -> Result<Disk, String> {
+        let boot_files = match sb {
+            Some(sb) => sb.signed_boot_files(self.arch, efi, work)?,
+            None => vec![(efi.to_path_buf(), self.arch.boot_file().to_string())],
+        };
+
+        match &self.layout {
+            Some(path) => {
+                let [(loader, _)] = boot_files.as_slice() else {
+                    return Err("--shim needs the ESP directory (drop --layout)".into());
+                };
+                let img = work.join("disk.img");
+                image::write_image(
+                    &Layout::load(path, self.arch, &self.vars)?,
+                    Some(loader),
+                    &img,
+                )?;
+                Ok(Disk::Image(img))
+            }
+            None => {
+                let esp = work.join("esp");
+                let mut files: Vec<(Option<PathBuf>, String)> = boot_files
+                    .into_iter()
+                    .map(|(src, dest)| (Some(src), dest))
+                    .collect();
+                files.extend([
+                    (self.kernel.clone(), ESP_KERNEL.to_string()),
+                    (self.capsule.clone(), ESP_CAPSULE.to_string()),
+                    (self.config.clone(), ESP_CONFIG.to_string()),
+                ]);
+                assemble_esp(&esp, &files)?;
+                Ok(Disk::EspDir(esp))
+            }
+        }
+    }
+}
+
+pub(crate) fn run(raw: &[String]) -> Result<(), String> {
+    let switches = [
+        BootInputs::SWITCHES,
+        SecureBoot::SWITCHES,
+        &["verbose", "expect-markers"],
+    ]
+    .concat();
+    let mut f = Flags::parse(raw, &switches)?;
+    let verbose = f.switch("verbose");
+    let expect_markers = f.switch("expect-markers");
+    let inputs = BootInputs::from_flags(&mut f)?;
+    let sb = SecureBoot::from_flags(&mut f)?;
+    let mut scenarios: Vec<PathBuf> = f
+        .values("scenario")
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+    let ovmf = f.value("ovmf");
+    let ovmf_vars = f.value("ovmf-vars");
+    f.finish()?;
+
+    // Parse everything up front so a typo doesn't cost a full build
+    if scenarios.is_empty() {
+        let default = match sb {
+            Some(_) => SECURE_BOOT_SCENARIO,
+            None => DEFAULT_SCENARIO,
+        };
+        scenarios.push(crate::workspace_root().join(default));
+    }
+    let mut scenarios = scenarios
+        .iter()
+        .map(|p| Scenario::load(p))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut unsigned = match sb {
+        Some(_) => Some(Scenario::load(
+            &crate::workspace_root().join(SB_UNSIGNED_SCENARIO),
+        )?),
+        None => None,
+    };
+    // Nothing in this tree emits the @nonos records yet; asserting on them would fail every
+    // run by construction
+    if !expect_markers {
+        for sc in scenarios.iter_mut().chain(unsigned.as_mut()) {
+            let skipped = sc.skip_markers();
+            if skipped > 0 {
+                println!(
+                    "[*] {}: skipping {skipped} record assertion(s) (--expect-markers to enforce)",
+                    sc.name
+                );
+            }
+        }
+    }
+
+    let work = crate::xtask_dir().join("test-boot");
+    let mut firmware = Firmware::locate(inputs.arch, sb.is_some(), ovmf, ovmf_vars)?;
+    if let Some(sb) = &sb {
+        sb.ensure_keys()?;
+        firmware = sb.enrolled(&firmware, &work)?;
+    }
+    let efi = inputs.loader()?;
+    let mut machine = Machine {
+        disk: inputs.prepare(&efi, &work, sb.as_ref())?,
+        arch: inputs.arch,
+        qemu: inputs.qemu.clone(),
+        firmware,
+        memory_mb: 512,
+        cpus: 2,
+        secure_boot: sb.is_some(),
+    };
+
+    // A scenario left with nothing to reach is not booted: it would "pass" a hang
+    let mut failed = 0usize;
+    let mut skipped = 0usize;
+    for sc in &scenarios {
+        if !sc.asserts_progress() {
+            println!(
+                "[-] {}: skipped (no markers; --expect-markers to enforce)",
+                sc.name
+            );
+            skipped += 1;
+        } else if !run_scenario(&machine, &work, sc, verbose)? {
+            failed += 1;
+        }
+    }
+
+    // The enforcing half of Secure Boot: the same loader, unsigned, must never run
+    let mut total = scenarios.len();
+    if let Some(sc) = &unsigned {
+        total += 1;
+        machine.disk = inputs.prepare(&efi, &work.join("unsigned"), None)?;
+        if !run_scenario(&machine, &work, sc, verbose)? {
+            failed += 1;
+        }
+    }
+
+    match (failed, skipped) {
+        (0, 0) => Ok(()),
+        (0, _) => Err(format!(
+            "{skipped} of {total} scenario(s) skipped with nothing left to assert"
+        )),
+        _ => Err(format!(
+            "{failed} of {total} scenario(s) failed, {skipped} skipped"
+        )),
+    }
+}
+
+/// Boot once for `sc` and report; `Ok(false)` means the scenario failed.
+fn run_scenario(
+    machine: &Machine,
+    work: &Path,
+    sc: &Scenario,
+    verbose: bool,
+) -> Result<bool, String> {
+    println!("[*] scenario {}", sc.name);
+    let log = qemu::boot(
+        machine,
+        &work.join(&sc.name),
+        sc.timeout,
+        sc.until.as_deref(),
+        verbose,
+    )?;
+
+    let mut failures = sc.check(&log.lines);
+    if sc.until.is_some() && !log.reached_marker {
+        let why = if log.timed_out {
+            "timed out"
+        } else {
+            "VM exited"
+        };
+        failures.push(format!(
+            "{why} before `{}`",
+            sc.until.as_deref().unwrap_or_default()
+        ));
+    }
+
+    // Machine-readable copy for CI, next to serial.log
+    let records = serial::parse_all(&log.lines);
+    let json = work.join(&sc.name).join("records.json");
+    fs::write(&json, serial::to_json(&records))
+        .map_err(|e| format!("write {}: {e}", json.display()))?;
+
+    if failures.is_empty() {
+        println!(
+            "[+] {}: pass ({} serial lines, {} records)",
+            sc.name,
+            log.lines.len(),
+            records.len()
+        );
+        return Ok(true);
+    }
+    println!("[-] {}: FAIL", sc.name);
+    for msg in &failures {
+        println!("      {msg}");
+    }
+    println!(
+        "      serial log: {}",
+        work.join(&sc.name).join("serial.log").display()
+    );
+    Ok(false)
+}
+
+/// Recreate `esp` from scratch with each provided file at its ESP path.
+fn assemble_esp(esp: &Path, files: &[(Option<PathBuf>, String)]) -> Result<(), String> {
+    if esp.exists() {
+        fs::remove_dir_all(esp).map_err(|e| format!("clean {}: {e}", esp.display()))?;
+    }
+    for (src, dest) in files {
+        let Some(src) = src else { continue };
+        let dest = esp.join(dest);
+        if let Some(dir) = dest.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("create {}: {e}", dir.display()))?;
+        }
+        fs::copy(src, &dest).map_err(|e| format!("copy {}: {e}", src.display()))?;
+    }
+    Ok(())
+}